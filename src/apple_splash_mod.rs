@@ -0,0 +1,128 @@
+//! apple_splash_mod.rs
+//! iOS ignores the manifest for the splash screen. It needs `apple-touch-startup-image` links
+//! with the exact size of the device in media queries, for portrait and landscape.
+//! The splash images are the source icon centered on the background color.
+//! Pure Rust, no javascript objects here.
+
+use crate::utils_mod::html_encode;
+
+/// the devices: width and height in css pixels and the pixel ratio
+const APPLE_DEVICES: &[(u32, u32, u32)] = &[
+    // iPad Pro 12.9"
+    (1024, 1366, 2),
+    // iPad Pro 11"
+    (834, 1194, 2),
+    // iPad Air 10.9"
+    (820, 1180, 2),
+    // iPad Air 10.5"
+    (834, 1112, 2),
+    // iPad 10.2"
+    (810, 1080, 2),
+    // iPad 9.7", iPad mini 7.9"
+    (768, 1024, 2),
+    // iPad mini 8.3"
+    (744, 1133, 2),
+    // iPhone 14 Pro Max
+    (430, 932, 3),
+    // iPhone 14 Pro
+    (393, 852, 3),
+    // iPhone 14 Plus, 13 Pro Max, 12 Pro Max
+    (428, 926, 3),
+    // iPhone 14, 13, 13 Pro, 12, 12 Pro
+    (390, 844, 3),
+    // iPhone 13 mini, 12 mini, 11 Pro, XS, X
+    (375, 812, 3),
+    // iPhone 11 Pro Max, XS Max
+    (414, 896, 3),
+    // iPhone 11, XR
+    (414, 896, 2),
+    // iPhone 8 Plus, 7 Plus, 6s Plus
+    (414, 736, 3),
+    // iPhone SE 2nd gen, 8, 7, 6s
+    (375, 667, 2),
+    // iPhone SE 1st gen, iPod touch
+    (320, 568, 2),
+];
+
+/// the artwork is a third of the shorter side of the splash image
+pub const SPLASH_ICON_RATIO: f64 = 1.0 / 3.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Orientation {
+    Portrait,
+    Landscape,
+}
+
+impl Orientation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Orientation::Portrait => "portrait",
+            Orientation::Landscape => "landscape",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SplashImage {
+    /// the size of the image in real pixels
+    pub width: u32,
+    pub height: u32,
+    /// the device in css pixels, always in portrait
+    pub device_width: u32,
+    pub device_height: u32,
+    pub pixel_ratio: u32,
+    pub orientation: Orientation,
+}
+
+impl SplashImage {
+    /// the path relative to the pwa folder
+    pub fn src(&self) -> String {
+        format!("icons/splash/apple-splash-{}x{}.png", self.width, self.height)
+    }
+    /// the size of the centered icon
+    pub fn icon_size(&self) -> u32 {
+        ((self.width.min(self.height) as f64) * SPLASH_ICON_RATIO).round() as u32
+    }
+}
+
+/// the full matrix of portrait and landscape splash images
+pub fn apple_splash_images() -> Vec<SplashImage> {
+    let mut images = vec![];
+    for (device_width, device_height, pixel_ratio) in APPLE_DEVICES.iter() {
+        let (portrait_width, portrait_height) = (device_width * pixel_ratio, device_height * pixel_ratio);
+        for orientation in [Orientation::Portrait, Orientation::Landscape].iter() {
+            let (width, height) = match orientation {
+                Orientation::Portrait => (portrait_width, portrait_height),
+                Orientation::Landscape => (portrait_height, portrait_width),
+            };
+            images.push(SplashImage {
+                width,
+                height,
+                device_width: *device_width,
+                device_height: *device_height,
+                pixel_ratio: *pixel_ratio,
+                orientation: *orientation,
+            });
+        }
+    }
+    // return
+    images
+}
+
+/// the `<link>` tags for the `<head>` of index.html, indented like the other link tags
+pub fn apple_splash_link_tags(images: &[SplashImage]) -> String {
+    let mut html = String::from("            <!-- splash screen for iOS -->\n");
+    for image in images.iter() {
+        html.push_str(&format!(
+            r#"            <link rel="apple-touch-startup-image" media="screen and (device-width: {}px) and (device-height: {}px) and (-webkit-device-pixel-ratio: {}) and (orientation: {})" href="{}">"#,
+            image.device_width,
+            image.device_height,
+            image.pixel_ratio,
+            image.orientation.as_str(),
+            html_encode(&image.src())
+        ));
+        html.push('\n');
+    }
+    // return
+    html
+}
@@ -0,0 +1,21 @@
+//! error_mod.rs
+//! All the errors of this helper in one enum.
+//! The messages are readable for the user, because they are shown in the page.
+
+#[derive(thiserror::Error, Debug)]
+pub enum HelperError {
+    #[error("The selected file is not a valid image: {0}")]
+    ImageDecode(String),
+    #[error("Cannot encode the image: {0}")]
+    ImageEncode(String),
+    #[error("Cannot create the zip file: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("Cannot write the file: {0}")]
+    Io(#[from] std::io::Error),
+    /// invalid data typed by the user
+    #[error("{0}")]
+    Input(String),
+    /// something wrong with the dom, local_storage or other javascript object
+    #[error("Browser error: {0}")]
+    Js(String),
+}
@@ -0,0 +1,338 @@
+//! icon_set_mod.rs
+//! The icon set is declared as data: a list of entries (size, file name, purpose, platforms).
+//! The same list is used to resize the png files, to write the manifest.json icons array
+//! and the <link> tags in index.html, so they can never drift apart.
+//! The text format is one entry per line, for easy editing in a textarea and storing in local_storage:
+//! `size file_name purpose platform,platform`
+//! Pure Rust, no javascript objects here.
+
+use crate::error_mod::HelperError;
+use crate::image_variant_mod::ImageVariant;
+use crate::utils_mod::html_encode;
+use crate::web_app_manifest_mod::ManifestImage;
+
+/// the default icon set: the png favicons, the Android and iOS icons, the maskable icons 192 and 512
+/// for the manifest and the square Windows tiles
+pub const DEFAULT_ICON_SET: &str = r#"32 icon-032.png any favicon
+72 icon-072.png any manifest
+96 icon-096.png any manifest
+120 icon-120.png any ios
+128 icon-128.png any favicon,manifest
+144 icon-144.png any manifest
+152 icon-152.png any ios,manifest
+167 icon-167.png any ios
+180 icon-180.png any ios
+192 icon-192.png any favicon,manifest
+196 icon-196.png any shortcut
+512 icon-512.png any manifest
+192 icon-maskable-192.png maskable manifest
+512 icon-maskable-512.png maskable manifest
+70 mstile-70x70.png any windows
+150 mstile-150x150.png any windows
+310 mstile-310x310.png any windows"#;
+
+/// the wide Windows tile is not square, it is created beside the icon set when there are windows tiles
+pub const WIDE_TILE_FILE_NAME: &str = "mstile-310x150.png";
+
+/// the svg source is copied beside the icon set with this name
+pub const SVG_ICON_FILE_NAME: &str = "icon.svg";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IconPurpose {
+    Any,
+    Maskable,
+}
+
+impl IconPurpose {
+    /// the value used in the text format and in the manifest.json "purpose" field
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IconPurpose::Any => "any",
+            IconPurpose::Maskable => "maskable",
+        }
+    }
+}
+
+/// where is the icon referenced from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IconPlatform {
+    /// <link rel="icon"> in index.html
+    Favicon,
+    /// <link rel="shortcut icon"> in index.html, used by old Android browsers
+    Shortcut,
+    /// <link rel="apple-touch-icon"> in index.html
+    Ios,
+    /// icons array in manifest.json
+    Manifest,
+    /// square tiles in browserconfig.xml for the pinned sites in Windows, sizes 70, 150 and 310
+    Windows,
+}
+
+#[derive(Clone, Debug)]
+pub struct IconEntry {
+    pub size: u32,
+    pub file_name: String,
+    pub purpose: IconPurpose,
+    pub platforms: Vec<IconPlatform>,
+}
+
+impl IconEntry {
+    /// the path relative to the pwa folder
+    pub fn src(&self) -> String {
+        format!("icons/{}", self.file_name)
+    }
+    pub fn has_platform(&self, platform: IconPlatform) -> bool {
+        self.platforms.contains(&platform)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IconSet {
+    pub entries: Vec<IconEntry>,
+}
+
+impl IconSet {
+    /// parse the text format, one entry per line. Empty lines and lines starting with # are ignored.
+    pub fn parse(text: &str) -> Result<IconSet, HelperError> {
+        let mut entries = vec![];
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let entry = parse_line(line).map_err(|err| HelperError::Input(format!("Icon set line {}: {}", line_number + 1, err)))?;
+            entries.push(entry);
+        }
+        if entries.is_empty() {
+            return Err(HelperError::Input("The icon set is empty.".to_string()));
+        }
+        check_file_names(&entries)?;
+        // return
+        Ok(IconSet { entries })
+    }
+
+    /// the icons array for manifest.json
+    pub fn manifest_icons(&self) -> Vec<ManifestImage> {
+        self.entries
+            .iter()
+            .filter(|e| e.has_platform(IconPlatform::Manifest))
+            .map(|entry| ManifestImage {
+                src: entry.src(),
+                sizes: Some(format!("{}x{}", entry.size, entry.size)),
+                mime_type: Some("image/png".to_string()),
+                purpose: Some(entry.purpose.as_str().to_string()),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// the `<link>` tags for the `<head>` of index.html
+    pub fn html_link_tags(&self) -> String {
+        let mut links = String::new();
+        for (platform, comment) in [
+            (IconPlatform::Favicon, "favicons generic"),
+            (IconPlatform::Shortcut, "favicons Android"),
+            (IconPlatform::Ios, "favicons iOS"),
+        ]
+        .iter()
+        {
+            let mut tags = String::new();
+            for entry in self.entries.iter().filter(|e| e.has_platform(*platform)) {
+                let src = html_encode(&entry.src());
+                let tag = match platform {
+                    IconPlatform::Favicon => format!(
+                        r#"<link rel="icon" type="image/png" href="{}" sizes="{}x{}">"#,
+                        src, entry.size, entry.size
+                    ),
+                    IconPlatform::Shortcut => format!(r#"<link rel="shortcut icon" href="{}" sizes="{}x{}">"#, src, entry.size, entry.size),
+                    _ => format!(
+                        r#"<link rel="apple-touch-icon" href="{}" sizes="{}x{}">"#,
+                        src, entry.size, entry.size
+                    ),
+                };
+                tags.push_str(&format!("            {}\n", tag));
+            }
+            if !tags.is_empty() {
+                links.push_str(&format!("            <!-- {} -->\n{}", comment, tags));
+            }
+        }
+        // return
+        links
+    }
+
+    pub fn has_windows_tiles(&self) -> bool {
+        self.entries.iter().any(|e| e.has_platform(IconPlatform::Windows))
+    }
+
+    /// browserconfig.xml for the pinned sites in Windows
+    pub fn browserconfig_xml(&self, tile_color: &str) -> String {
+        let mut tiles = String::new();
+        for entry in self.entries.iter().filter(|e| e.has_platform(IconPlatform::Windows)) {
+            if let Some(element) = windows_tile_element(entry.size) {
+                tiles.push_str(&format!("            <{} src=\"{}\"/>\n", element, html_encode(&entry.src())));
+            }
+        }
+        format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<browserconfig>
+    <msapplication>
+        <tile>
+{}            <wide310x150logo src="icons/{}"/>
+            <TileColor>{}</TileColor>
+        </tile>
+    </msapplication>
+</browserconfig>
+"#,
+            tiles,
+            WIDE_TILE_FILE_NAME,
+            html_encode(tile_color)
+        )
+    }
+}
+
+/// the element in browserconfig.xml for the square tile size
+fn windows_tile_element(size: u32) -> Option<&'static str> {
+    match size {
+        70 => Some("square70x70logo"),
+        150 => Some("square150x150logo"),
+        310 => Some("square310x310logo"),
+        _ => None,
+    }
+}
+
+/// the file names must be unique and must not clash with the files generated beside the icon set:
+/// the wide tile, the svg icon and the webp and avif variants of the png files
+fn check_file_names(entries: &[IconEntry]) -> Result<(), HelperError> {
+    let mut generated = vec![WIDE_TILE_FILE_NAME.to_string(), SVG_ICON_FILE_NAME.to_string()];
+    for entry in entries.iter() {
+        for variant in [ImageVariant::Webp, ImageVariant::Avif].iter() {
+            generated.push(variant.src(&entry.file_name));
+        }
+    }
+    let mut file_names: Vec<&str> = vec![];
+    for entry in entries.iter() {
+        if file_names.contains(&entry.file_name.as_str()) {
+            return Err(HelperError::Input(format!(
+                "Icon set: the file name `{}` is used twice.",
+                entry.file_name
+            )));
+        }
+        if generated.contains(&entry.file_name) {
+            return Err(HelperError::Input(format!(
+                "Icon set: the file name `{}` is reserved for a generated file.",
+                entry.file_name
+            )));
+        }
+        file_names.push(&entry.file_name);
+    }
+    Ok(())
+}
+
+/// parse one line: `size file_name purpose platform,platform`
+fn parse_line(line: &str) -> Result<IconEntry, String> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() != 4 {
+        return Err(format!("expected `size file_name purpose platforms`, found `{}`", line));
+    }
+    let size: u32 = fields[0].parse().map_err(|_| format!("`{}` is not a valid size", fields[0]))?;
+    if size == 0 {
+        return Err("size must be bigger than 0".to_string());
+    }
+    let file_name = fields[1].to_string();
+    if file_name.contains('/') || file_name.contains('\\') || file_name.contains("..") {
+        return Err(format!("`{}` must be a plain file name", file_name));
+    }
+    let purpose = match fields[2] {
+        "any" => IconPurpose::Any,
+        "maskable" => IconPurpose::Maskable,
+        other => return Err(format!("unknown purpose `{}`, expected any or maskable", other)),
+    };
+    let mut platforms = vec![];
+    for platform in fields[3].split(',') {
+        let platform = match platform {
+            "favicon" => IconPlatform::Favicon,
+            "shortcut" => IconPlatform::Shortcut,
+            "ios" => IconPlatform::Ios,
+            "manifest" => IconPlatform::Manifest,
+            "windows" => IconPlatform::Windows,
+            other => {
+                return Err(format!(
+                    "unknown platform `{}`, expected favicon, shortcut, ios, manifest or windows",
+                    other
+                ))
+            }
+        };
+        if platform == IconPlatform::Windows && windows_tile_element(size).is_none() {
+            return Err(format!("windows tile size {} must be 70, 150 or 310", size));
+        }
+        platforms.push(platform);
+    }
+    // return
+    Ok(IconEntry {
+        size,
+        file_name,
+        purpose,
+        platforms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(text: &str) -> String {
+        IconSet::parse(text).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn the_default_icon_set_parses() {
+        let icon_set = IconSet::parse(DEFAULT_ICON_SET).unwrap();
+        assert_eq!(icon_set.entries.len(), 17);
+        assert!(icon_set.has_windows_tiles());
+        assert_eq!(icon_set.manifest_icons().len(), 9);
+    }
+
+    #[test]
+    fn parse_one_entry() {
+        let icon_set = IconSet::parse("# comment\n\n  152 icon-152.png maskable ios,manifest  \n").unwrap();
+        let entry = &icon_set.entries[0];
+        assert_eq!(entry.size, 152);
+        assert_eq!(entry.file_name, "icon-152.png");
+        assert_eq!(entry.src(), "icons/icon-152.png");
+        assert_eq!(entry.purpose, IconPurpose::Maskable);
+        assert_eq!(entry.platforms, vec![IconPlatform::Ios, IconPlatform::Manifest]);
+    }
+
+    #[test]
+    fn parse_errors_have_the_line_number() {
+        assert!(parse_error("32 a.png any favicon\nx b.png any favicon").contains("line 2: `x` is not a valid size"));
+        assert!(parse_error("0 a.png any favicon").contains("line 1: size must be bigger than 0"));
+        assert!(parse_error("32 a.png any").contains("line 1: expected `size file_name purpose platforms`"));
+        assert!(parse_error("32 ../a.png any favicon").contains("must be a plain file name"));
+        assert!(parse_error("32 a.png round favicon").contains("unknown purpose `round`"));
+        assert!(parse_error("32 a.png any favicon,desktop").contains("unknown platform `desktop`"));
+        assert!(parse_error("64 a.png any windows").contains("windows tile size 64"));
+    }
+
+    #[test]
+    fn empty_icon_set_is_an_error() {
+        assert!(parse_error("# only a comment\n\n").contains("The icon set is empty."));
+    }
+
+    #[test]
+    fn duplicate_file_names_are_rejected() {
+        assert!(parse_error("32 a.png any favicon\n64 a.png any manifest").contains("`a.png` is used twice"));
+    }
+
+    #[test]
+    fn generated_file_names_are_rejected() {
+        for file_name in [WIDE_TILE_FILE_NAME, SVG_ICON_FILE_NAME].iter() {
+            let message = parse_error(&format!("32 {} any favicon", file_name));
+            assert!(message.contains("is reserved for a generated file"), "{}", message);
+        }
+        assert!(parse_error("32 a.png any favicon\n64 a.webp any manifest").contains("`a.webp` is reserved"));
+        assert!(parse_error("64 a.avif any manifest\n32 a.png any favicon").contains("`a.avif` is reserved"));
+        // without the png of the same name it is just a strange file name
+        assert!(IconSet::parse("64 a.webp any manifest").is_ok());
+    }
+}
@@ -0,0 +1,569 @@
+//! image_processing_mod.rs
+//! Image manipulation on top of the crate `image`, beyond the simple resize.
+//! The svg source is rasterized with the crate `resvg` in every target size.
+//! Pure Rust, no javascript objects here.
+
+use crate::error_mod::HelperError;
+use image::GenericImageView;
+
+/// The maskable icon must have the important artwork inside the safe zone,
+/// a circle with the diameter of 80% of the icon size. <https://web.dev/maskable-icon/>
+const MASKABLE_SAFE_ZONE: f64 = 0.8;
+
+/// background color of the maskable icon, None is transparent
+pub type Background = Option<image::Rgba<u8>>;
+
+/// the source image: a raster image is downscaled, a svg is rasterized directly in the target size
+pub enum SourceImage {
+    Raster(image::DynamicImage),
+    /// the view is the area of the svg that is rendered, in svg units
+    Svg(Box<resvg::usvg::Tree>, ViewRect),
+}
+
+/// an area of the svg in svg units
+#[derive(Clone, Copy, Debug)]
+pub struct ViewRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// the crop rectangle in fractions of the source width and height, from 0.0 to 1.0
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CropRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl CropRect {
+    /// parse the text format `x y width height`, empty means no crop
+    pub fn parse(text: &str) -> Result<Option<CropRect>, HelperError> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(None);
+        }
+        let error = || {
+            HelperError::Input(format!(
+                "Crop rectangle `{}` must be `x y width height` as fractions from 0 to 1",
+                text
+            ))
+        };
+        let values = text
+            .split_whitespace()
+            .map(|value| value.parse::<f64>().map_err(|_| error()))
+            .collect::<Result<Vec<f64>, HelperError>>()?;
+        // `NaN` and `inf` are valid f64, but not fractions
+        if values.len() != 4 || values.iter().any(|value| !value.is_finite()) {
+            return Err(error());
+        }
+        let rect = CropRect {
+            x: values[0],
+            y: values[1],
+            width: values[2],
+            height: values[3],
+        };
+        if rect.x < 0.0
+            || rect.y < 0.0
+            || rect.width <= 0.0
+            || rect.height <= 0.0
+            || rect.x + rect.width > 1.0001
+            || rect.y + rect.height > 1.0001
+        {
+            return Err(error());
+        }
+        // return
+        Ok(Some(rect))
+    }
+
+    /// the text format stored in local_storage
+    pub fn to_text(&self) -> String {
+        format!("{:.4} {:.4} {:.4} {:.4}", self.x, self.y, self.width, self.height)
+    }
+}
+
+/// the resampling filter of the resize, pixel-art scales only by integer factors
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum ResizeFilter {
+    Nearest,
+    Triangle,
+    CatmullRom,
+    Gaussian,
+    #[default]
+    Lanczos3,
+    /// integer nearest-neighbour scaling with transparent padding, the retro icons stay crisp
+    PixelArt,
+}
+
+impl ResizeFilter {
+    pub fn parse(text: &str) -> Result<ResizeFilter, HelperError> {
+        match text {
+            "nearest" => Ok(ResizeFilter::Nearest),
+            "triangle" => Ok(ResizeFilter::Triangle),
+            "catmull-rom" => Ok(ResizeFilter::CatmullRom),
+            "gaussian" => Ok(ResizeFilter::Gaussian),
+            "lanczos3" => Ok(ResizeFilter::Lanczos3),
+            "pixel-art" => Ok(ResizeFilter::PixelArt),
+            other => Err(HelperError::Input(format!(
+                "Resize filter `{}` is unknown, expected nearest, triangle, catmull-rom, gaussian, lanczos3 or pixel-art",
+                other
+            ))),
+        }
+    }
+
+    fn filter_type(&self) -> image::imageops::FilterType {
+        match self {
+            ResizeFilter::Nearest => image::imageops::FilterType::Nearest,
+            ResizeFilter::Triangle => image::imageops::FilterType::Triangle,
+            ResizeFilter::CatmullRom => image::imageops::FilterType::CatmullRom,
+            ResizeFilter::Gaussian => image::imageops::FilterType::Gaussian,
+            ResizeFilter::Lanczos3 => image::imageops::FilterType::Lanczos3,
+            ResizeFilter::PixelArt => image::imageops::FilterType::Nearest,
+        }
+    }
+}
+
+/// the unsharp mask is only for the sizes below 64px
+const SHARPEN_MAX_SIZE: u32 = 64;
+/// the blur radius of the unsharp mask, small icons need a small radius
+const SHARPEN_SIGMA: f32 = 0.6;
+/// the strongest unsharp mask
+const SHARPEN_MAX_STRENGTH: f32 = 3.0;
+
+/// how the raster source is resized
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct ResizeOptions {
+    pub filter: ResizeFilter,
+    /// resize in linear light instead of sRGB, the edges are not darkened
+    pub linear_light: bool,
+    /// the strength of the unsharp mask for the sizes below 64px, 0.0 is off
+    pub sharpen: f32,
+}
+
+/// parse the unsharp mask strength, empty is off
+pub fn parse_sharpen_strength(text: &str) -> Result<f32, HelperError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(0.0);
+    }
+    match text.parse::<f32>() {
+        Ok(strength) if (0.0..=SHARPEN_MAX_STRENGTH).contains(&strength) => Ok(strength),
+        _ => Err(HelperError::Input(format!(
+            "Sharpen strength `{}` must be a number from 0 to {}",
+            text, SHARPEN_MAX_STRENGTH
+        ))),
+    }
+}
+
+/// how to make a non-square source square
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SquareFix {
+    /// the whole image centered on a transparent square
+    Pad,
+    /// the centered square, the edges are cut
+    Crop,
+}
+
+impl SourceImage {
+    /// parse the svg, text elements are not rendered, because there are no fonts in wasm
+    pub fn from_svg(svg_bytes: &[u8]) -> Result<SourceImage, HelperError> {
+        let tree = resvg::usvg::Tree::from_data(svg_bytes, &resvg::usvg::Options::default())
+            .map_err(|err| HelperError::ImageDecode(format!("svg: {}", err)))?;
+        let view = ViewRect {
+            x: 0.0,
+            y: 0.0,
+            width: tree.size().width(),
+            height: tree.size().height(),
+        };
+        // return
+        Ok(SourceImage::Svg(Box::new(tree), view))
+    }
+
+    /// width and height in pixels, for svg in svg units
+    pub fn dimensions(&self) -> (u32, u32) {
+        match self {
+            SourceImage::Raster(img) => (img.width(), img.height()),
+            SourceImage::Svg(_, view) => (view.width.round() as u32, view.height.round() as u32),
+        }
+    }
+
+    /// the svg file of the view, None for a raster image.
+    /// The whole svg keeps the original bytes, a cropped or squared view is the parsed tree inside a svg with the view as viewBox.
+    pub fn to_svg_bytes(&self, original_bytes: &[u8]) -> Option<Vec<u8>> {
+        match self {
+            SourceImage::Raster(_) => None,
+            SourceImage::Svg(tree, view) => {
+                let size = tree.size();
+                if view.x == 0.0 && view.y == 0.0 && view.width == size.width() && view.height == size.height() {
+                    return Some(original_bytes.to_vec());
+                }
+                // the tree is written without viewBox, so its units are the units of the view
+                let svg = format!(
+                    "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"{} {} {} {}\">\n{}</svg>\n",
+                    view.width,
+                    view.height,
+                    view.x,
+                    view.y,
+                    view.width,
+                    view.height,
+                    tree.to_string(&resvg::usvg::WriteOptions::default())
+                );
+                Some(svg.into_bytes())
+            }
+        }
+    }
+
+    pub fn is_square(&self) -> bool {
+        let (width, height) = self.dimensions();
+        width == height
+    }
+
+    /// the image fits inside size x size, the aspect ratio is preserved
+    pub fn resize(&self, size: u32, resize_options: ResizeOptions) -> image::DynamicImage {
+        let filter = resize_options.filter;
+        match self {
+            // a hand-tuned source in the exact size is used pixel by pixel
+            SourceImage::Raster(img) if img.width() == size && img.height() == size => img.clone(),
+            SourceImage::Raster(img) if filter == ResizeFilter::PixelArt => pixel_art_resize(img, size),
+            SourceImage::Raster(img) => {
+                let resized = if resize_options.linear_light {
+                    linear_light_resize(img, size, filter.filter_type())
+                } else {
+                    img.resize(size, size, filter.filter_type())
+                };
+                // the small downscaled icons are soft
+                if resize_options.sharpen > 0.0 && size < SHARPEN_MAX_SIZE {
+                    unsharp_mask(&resized, resize_options.sharpen)
+                } else {
+                    resized
+                }
+            }
+            SourceImage::Svg(tree, view) => render_svg(tree, view, size),
+        }
+    }
+
+    /// crop the rectangle, before every resize
+    /// The rectangle rounded to pixels must not be empty.
+    pub fn crop(self, rect: &CropRect) -> Result<SourceImage, HelperError> {
+        match self {
+            SourceImage::Raster(img) => {
+                let (width, height) = (img.width(), img.height());
+                // the start stays inside the image, so the crop_imm cannot shrink to nothing
+                let x = ((rect.x * width as f64).round() as u32).min(width.saturating_sub(1));
+                let y = ((rect.y * height as f64).round() as u32).min(height.saturating_sub(1));
+                let crop_width = ((rect.width * width as f64).round() as u32).min(width - x);
+                let crop_height = ((rect.height * height as f64).round() as u32).min(height - y);
+                if crop_width == 0 || crop_height == 0 {
+                    return Err(HelperError::Input(format!(
+                        "Crop rectangle `{} {} {} {}` is empty on the source image {}x{}",
+                        rect.x, rect.y, rect.width, rect.height, width, height
+                    )));
+                }
+                Ok(SourceImage::Raster(img.crop_imm(x, y, crop_width, crop_height)))
+            }
+            SourceImage::Svg(tree, view) => {
+                let cropped = ViewRect {
+                    x: view.x + rect.x as f32 * view.width,
+                    y: view.y + rect.y as f32 * view.height,
+                    width: rect.width as f32 * view.width,
+                    height: rect.height as f32 * view.height,
+                };
+                Ok(SourceImage::Svg(tree, cropped))
+            }
+        }
+    }
+
+    /// pad or crop the source to a centered square
+    pub fn to_square(self, square_fix: SquareFix) -> SourceImage {
+        match self {
+            SourceImage::Raster(img) => {
+                let (width, height) = (img.width(), img.height());
+                if width == height {
+                    return SourceImage::Raster(img);
+                }
+                let img = match square_fix {
+                    SquareFix::Pad => {
+                        let side = width.max(height);
+                        let mut canvas = image::RgbaImage::new(side, side);
+                        image::imageops::overlay(&mut canvas, &img.into_rgba8(), (side - width) / 2, (side - height) / 2);
+                        image::DynamicImage::ImageRgba8(canvas)
+                    }
+                    SquareFix::Crop => {
+                        let side = width.min(height);
+                        img.crop_imm((width - side) / 2, (height - side) / 2, side, side)
+                    }
+                };
+                SourceImage::Raster(img)
+            }
+            SourceImage::Svg(tree, view) => {
+                let side = match square_fix {
+                    SquareFix::Pad => view.width.max(view.height),
+                    SquareFix::Crop => view.width.min(view.height),
+                };
+                let square = ViewRect {
+                    x: view.x + (view.width - side) / 2.0,
+                    y: view.y + (view.height - side) / 2.0,
+                    width: side,
+                    height: side,
+                };
+                SourceImage::Svg(tree, square)
+            }
+        }
+    }
+}
+
+/// scale by an integer factor with nearest-neighbour, so every source pixel becomes a square block.
+/// The rest up to the fitted size is transparent padding around the centered image.
+fn pixel_art_resize(img: &image::DynamicImage, size: u32) -> image::DynamicImage {
+    let (width, height) = (img.width(), img.height());
+    let longest = width.max(height);
+    let (scaled_width, scaled_height) = if longest <= size {
+        let factor = size / longest;
+        (width * factor, height * factor)
+    } else {
+        // downscale takes every n-th pixel
+        let factor = longest.div_ceil(size);
+        ((width / factor).max(1), (height / factor).max(1))
+    };
+    let scaled = image::imageops::resize(&img.to_rgba8(), scaled_width, scaled_height, image::imageops::FilterType::Nearest);
+    // the same fitted size as the other filters
+    let (fitted_width, fitted_height) = fitted_dimensions(width, height, size);
+    let (fitted_width, fitted_height) = (fitted_width.max(scaled_width), fitted_height.max(scaled_height));
+    let mut canvas = image::RgbaImage::new(fitted_width, fitted_height);
+    image::imageops::overlay(
+        &mut canvas,
+        &scaled,
+        (fitted_width - scaled_width) / 2,
+        (fitted_height - scaled_height) / 2,
+    );
+    // return
+    image::DynamicImage::ImageRgba8(canvas)
+}
+
+/// the size that fits inside size x size with the same aspect ratio
+fn fitted_dimensions(width: u32, height: u32, size: u32) -> (u32, u32) {
+    let scale = (size as f64 / width as f64).min(size as f64 / height as f64);
+    // return
+    (
+        ((width as f64 * scale).round() as u32).max(1),
+        ((height as f64 * scale).round() as u32).max(1),
+    )
+}
+
+/// resize in linear light with premultiplied alpha, so the edges and the thin lines are not darkened.
+/// The 16 bits per channel keep the precision of the dark colors.
+fn linear_light_resize(img: &image::DynamicImage, size: u32, filter_type: image::imageops::FilterType) -> image::DynamicImage {
+    let (width, height) = fitted_dimensions(img.width(), img.height(), size);
+    let to_linear: Vec<f32> = (0..=255).map(|value| srgb_to_linear(value as f32 / 255.0)).collect();
+    let rgba = img.to_rgba8();
+    let mut linear: image::ImageBuffer<image::Rgba<u16>, Vec<u16>> = image::ImageBuffer::new(rgba.width(), rgba.height());
+    for (out, pixel) in linear.pixels_mut().zip(rgba.pixels()) {
+        let alpha = pixel[3] as f32 / 255.0;
+        let channel = |value: u8| (to_linear[value as usize] * alpha * 65535.0).round() as u16;
+        *out = image::Rgba([channel(pixel[0]), channel(pixel[1]), channel(pixel[2]), pixel[3] as u16 * 257]);
+    }
+    let resized = image::imageops::resize(&linear, width, height, filter_type);
+    let mut canvas = image::RgbaImage::new(width, height);
+    for (out, pixel) in canvas.pixels_mut().zip(resized.pixels()) {
+        let alpha = pixel[3] as f32 / 65535.0;
+        // fully transparent stays transparent black
+        if alpha > 0.0 {
+            let channel = |value: u16| (linear_to_srgb((value as f32 / 65535.0 / alpha).min(1.0)) * 255.0).round() as u8;
+            *out = image::Rgba([
+                channel(pixel[0]),
+                channel(pixel[1]),
+                channel(pixel[2]),
+                (alpha * 255.0).round() as u8,
+            ]);
+        }
+    }
+    // return
+    image::DynamicImage::ImageRgba8(canvas)
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// unsharp mask: the difference to the blurred image is added again with the strength.
+/// It works on the premultiplied colors, so the transparent pixels do not bleed into the edges. The alpha stays the same.
+fn unsharp_mask(img: &image::DynamicImage, strength: f32) -> image::DynamicImage {
+    let mut canvas = img.to_rgba8();
+    let mut premultiplied = canvas.clone();
+    for pixel in premultiplied.pixels_mut() {
+        let alpha = pixel[3] as u32;
+        for channel in pixel.0.iter_mut().take(3) {
+            *channel = (*channel as u32 * alpha / 255) as u8;
+        }
+    }
+    let blurred = image::imageops::blur(&premultiplied, SHARPEN_SIGMA);
+    for ((out, sharp), blur) in canvas.pixels_mut().zip(premultiplied.pixels()).zip(blurred.pixels()) {
+        let alpha = out[3] as f32;
+        if alpha > 0.0 {
+            for (channel, (sharp, blur)) in out.0.iter_mut().zip(sharp.0.iter().zip(blur.0.iter())).take(3) {
+                let value = *sharp as f32 + strength * (*sharp as f32 - *blur as f32);
+                *channel = (value.clamp(0.0, alpha) * 255.0 / alpha).round() as u8;
+            }
+        }
+    }
+    // return
+    image::DynamicImage::ImageRgba8(canvas)
+}
+
+/// rasterize the view of the svg with resvg, the pixels of tiny_skia are premultiplied
+fn render_svg(tree: &resvg::usvg::Tree, view: &ViewRect, size: u32) -> image::DynamicImage {
+    let scale = (size as f32 / view.width).min(size as f32 / view.height);
+    let width = ((view.width * scale).round() as u32).max(1);
+    let height = ((view.height * scale).round() as u32).max(1);
+    let mut canvas = image::RgbaImage::new(width, height);
+    if let Some(mut pixmap) = resvg::tiny_skia::Pixmap::new(width, height) {
+        let transform = resvg::tiny_skia::Transform::from_row(scale, 0.0, 0.0, scale, -view.x * scale, -view.y * scale);
+        resvg::render(tree, transform, &mut pixmap.as_mut());
+        for (pixel, color) in canvas.pixels_mut().zip(pixmap.pixels().iter()) {
+            let color = color.demultiply();
+            *pixel = image::Rgba([color.red(), color.green(), color.blue(), color.alpha()]);
+        }
+    }
+    // return
+    image::DynamicImage::ImageRgba8(canvas)
+}
+
+/// maskable icon: the source is resized into the safe zone and centered on the background color
+pub fn maskable_icon(source: &SourceImage, size: u32, background: Background, resize_options: ResizeOptions) -> image::DynamicImage {
+    let safe_size = ((size as f64) * MASKABLE_SAFE_ZONE).round() as u32;
+    centered_on_background(source, size, size, safe_size, background, resize_options)
+}
+
+/// the source is resized to artwork_size and centered on a canvas with the background color
+pub fn centered_on_background(
+    source: &SourceImage,
+    width: u32,
+    height: u32,
+    artwork_size: u32,
+    background: Background,
+    resize_options: ResizeOptions,
+) -> image::DynamicImage {
+    let artwork = source.resize(artwork_size, resize_options).into_rgba8();
+    let fill = background.unwrap_or(image::Rgba([0, 0, 0, 0]));
+    let mut canvas = image::RgbaImage::from_pixel(width, height, fill);
+    // the resize preserves the aspect ratio, so it is centered in both directions
+    let x = (width - artwork.width()) / 2;
+    let y = (height - artwork.height()) / 2;
+    image::imageops::overlay(&mut canvas, &artwork, x, y);
+    // return
+    image::DynamicImage::ImageRgba8(canvas)
+}
+
+/// parse color in the format #rgb, #rrggbb, #rrggbbaa or the word transparent
+pub fn parse_background(text: &str) -> Result<Background, HelperError> {
+    parse_color("Maskable icon background", text)
+}
+
+/// parse color in the format #rgb, #rrggbb, #rrggbbaa or the word transparent,
+/// the field name is for the error message
+pub fn parse_color(field_name: &str, text: &str) -> Result<Background, HelperError> {
+    let text = text.trim();
+    if text.eq_ignore_ascii_case("transparent") {
+        return Ok(None);
+    }
+    let error = || HelperError::Input(format!("{} `{}` is not a color like #rrggbb or transparent", field_name, text));
+    let hex = text.strip_prefix('#').ok_or_else(error)?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(error());
+    }
+    let digits: Vec<u8> = match hex.len() {
+        // #rgb is a short form of #rrggbb
+        3 => hex
+            .chars()
+            .flat_map(|c| vec![c, c])
+            .map(|c| c.to_digit(16).unwrap_or(0) as u8)
+            .collect(),
+        6 | 8 => hex.chars().map(|c| c.to_digit(16).unwrap_or(0) as u8).collect(),
+        _ => return Err(error()),
+    };
+    let channel = |i: usize| digits.get(2 * i).map(|high| high * 16 + digits[2 * i + 1]);
+    // return
+    Ok(Some(image::Rgba([
+        channel(0).unwrap_or(0),
+        channel(1).unwrap_or(0),
+        channel(2).unwrap_or(0),
+        channel(3).unwrap_or(255),
+    ])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crop_rect_parse_empty_is_no_crop() {
+        assert_eq!(CropRect::parse("").unwrap(), None);
+        assert_eq!(CropRect::parse("  ").unwrap(), None);
+    }
+
+    #[test]
+    fn crop_rect_parse_and_to_text() {
+        let rect = CropRect::parse(" 0.25 0  0.5 1 ").unwrap().unwrap();
+        assert_eq!(
+            rect,
+            CropRect {
+                x: 0.25,
+                y: 0.0,
+                width: 0.5,
+                height: 1.0
+            }
+        );
+        assert_eq!(rect.to_text(), "0.2500 0.0000 0.5000 1.0000");
+        assert_eq!(CropRect::parse(&rect.to_text()).unwrap(), Some(rect));
+    }
+
+    #[test]
+    fn crop_rect_parse_rejects_bad_rectangles() {
+        for text in [
+            "0 0 1",
+            "0 0 1 1 1",
+            "a b c d",
+            "-0.1 0 0.5 0.5",
+            "0 0 0 1",
+            "0 0 1 -1",
+            "0.6 0 0.5 1",
+            "0 0.5 1 0.6",
+            "NaN 0 1 1",
+            "0 0 inf 1",
+        ]
+        .iter()
+        {
+            assert!(CropRect::parse(text).is_err(), "{}", text);
+        }
+        // the rounding of to_text can add a little bit
+        assert!(CropRect::parse("0.5 0.5 0.50005 0.50005").is_ok());
+    }
+
+    #[test]
+    fn crop_of_a_raster_image() {
+        let source = SourceImage::Raster(image::DynamicImage::new_rgba8(100, 50));
+        let rect = CropRect::parse("0.25 0 0.5 1").unwrap().unwrap();
+        assert_eq!(source.crop(&rect).unwrap().dimensions(), (50, 50));
+    }
+
+    #[test]
+    fn crop_to_nothing_is_an_error() {
+        let source = SourceImage::Raster(image::DynamicImage::new_rgba8(100, 100));
+        let rect = CropRect::parse("0.99999 0 0.0001 1").unwrap().unwrap();
+        assert!(source.crop(&rect).is_err());
+    }
+}
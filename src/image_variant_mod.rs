@@ -0,0 +1,128 @@
+//! image_variant_mod.rs
+//! The optional WebP and AVIF variants of the icons. They are listed in the manifest with the correct `type`
+//! before the png of the same size, so the modern browsers can pick the smaller file. The png stays as fallback.
+//! A variant that is not smaller than the png is left out.
+//! WebP is lossless with the pure Rust crate `image-webp`. AVIF is lossy with the crate `ravif`,
+//! it needs the cargo feature `avif`.
+//! Pure Rust, no javascript objects here.
+
+use crate::error_mod::HelperError;
+use crate::web_app_manifest_mod::ManifestImage;
+
+/// the quality of the lossy avif from 1 to 100
+#[cfg(feature = "avif")]
+const AVIF_QUALITY: f32 = 80.0;
+/// the speed of the avif encoder from 1 (slow, small) to 10 (fast)
+#[cfg(feature = "avif")]
+const AVIF_SPEED: u8 = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageVariant {
+    Webp,
+    Avif,
+}
+
+impl ImageVariant {
+    /// the chosen variants, avif is the smallest so it is the first
+    pub fn chosen(webp: bool, avif: bool) -> Result<Vec<ImageVariant>, HelperError> {
+        let mut variants = vec![];
+        if avif {
+            if !cfg!(feature = "avif") {
+                return Err(HelperError::Input(
+                    "AVIF variants are not available, this build has no cargo feature `avif`".to_string(),
+                ));
+            }
+            variants.push(ImageVariant::Avif);
+        }
+        if webp {
+            variants.push(ImageVariant::Webp);
+        }
+        // return
+        Ok(variants)
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageVariant::Webp => "webp",
+            ImageVariant::Avif => "avif",
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ImageVariant::Webp => "image/webp",
+            ImageVariant::Avif => "image/avif",
+        }
+    }
+
+    /// the same path as the png with the extension of the variant
+    pub fn src(&self, png_src: &str) -> String {
+        let stem = png_src.strip_suffix(".png").unwrap_or(png_src);
+        format!("{}.{}", stem, self.extension())
+    }
+
+    pub fn encode(&self, img: &image::DynamicImage) -> Result<Vec<u8>, HelperError> {
+        match self {
+            ImageVariant::Webp => encode_webp(img),
+            ImageVariant::Avif => encode_avif(img),
+        }
+    }
+}
+
+/// lossless webp
+fn encode_webp(img: &image::DynamicImage) -> Result<Vec<u8>, HelperError> {
+    let rgba = img.to_rgba8();
+    let mut vec_u8: Vec<u8> = Vec::new();
+    image_webp::WebPEncoder::new(&mut vec_u8)
+        .encode(rgba.as_raw(), rgba.width(), rgba.height(), image_webp::ColorType::Rgba8)
+        .map_err(|err| HelperError::ImageEncode(err.to_string()))?;
+    // return
+    Ok(vec_u8)
+}
+
+/// lossy avif with alpha
+#[cfg(feature = "avif")]
+fn encode_avif(img: &image::DynamicImage) -> Result<Vec<u8>, HelperError> {
+    let rgba = img.to_rgba8();
+    let pixels: Vec<ravif::RGBA8> = rgba
+        .pixels()
+        .map(|pixel| ravif::RGBA8::new(pixel[0], pixel[1], pixel[2], pixel[3]))
+        .collect();
+    let encoded = ravif::Encoder::new()
+        .with_quality(AVIF_QUALITY)
+        .with_speed(AVIF_SPEED)
+        .encode_rgba(ravif::Img::new(&pixels[..], rgba.width() as usize, rgba.height() as usize))
+        .map_err(|err| HelperError::ImageEncode(err.to_string()))?;
+    // return
+    Ok(encoded.avif_file)
+}
+
+/// without the feature `avif` the variant cannot be chosen, see ImageVariant::chosen
+#[cfg(not(feature = "avif"))]
+fn encode_avif(_img: &image::DynamicImage) -> Result<Vec<u8>, HelperError> {
+    Err(HelperError::ImageEncode("this build has no cargo feature `avif`".to_string()))
+}
+
+/// every png icon of the manifest gets its variants listed before it.
+/// A variant is written into the zip only if it is smaller than the png, is_written checks it.
+pub fn manifest_icons_with_variants(
+    icons: Vec<ManifestImage>,
+    variants: &[ImageVariant],
+    is_written: impl Fn(&str) -> bool,
+) -> Vec<ManifestImage> {
+    let mut with_variants = vec![];
+    for icon in icons.into_iter() {
+        if icon.mime_type.as_deref() == Some("image/png") {
+            for variant in variants.iter().filter(|variant| is_written(&variant.src(&icon.src))) {
+                with_variants.push(ManifestImage {
+                    src: variant.src(&icon.src),
+                    mime_type: Some(variant.mime_type().to_string()),
+                    ..icon.clone()
+                });
+            }
+        }
+        with_variants.push(icon);
+    }
+    // return
+    with_variants
+}
@@ -1,16 +1,35 @@
 //! rust_wasm_helper_for_pwa
 //! lib.rs is just for the wasm_bindgen_start function and to connect to all the modules.
 //! and for the big doc comments
+//! The generator modules are public, so the crate can be used as a library from native Rust.
+//! The web page modules and the start function are compiled only for wasm.
 
+#[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
-mod prepare_zip_mod;
+pub mod apple_splash_mod;
+pub mod error_mod;
+pub mod icon_set_mod;
+pub mod image_processing_mod;
+pub mod image_variant_mod;
+pub mod png_optimization_mod;
+pub mod prepare_zip_mod;
+pub mod service_worker_mod;
+pub mod source_set_mod;
+pub mod source_validation_mod;
+pub mod utils_mod;
+pub mod web_app_manifest_mod;
+#[cfg(target_arch = "wasm32")]
 mod web_sys_mod;
+#[cfg(target_arch = "wasm32")]
+mod web_ui_mod;
 
+#[cfg(target_arch = "wasm32")]
 #[wasm_bindgen(start)]
 /// To start the Wasm application, wasm_bindgen runs this functions
 pub fn wasm_bindgen_start() -> Result<(), JsValue> {
-    prepare_zip_mod::start_function();
+    // the error is returned to javascript and shown in the console
+    web_ui_mod::start_function().map_err(|err| JsValue::from_str(&err.to_string()))?;
     // return
     Ok(())
 }
@@ -0,0 +1,212 @@
+//! rust_wasm_helper_for_pwa CLI
+//! The native binary generates the same files as the web page, but it can run in a CI pipeline.
+//! It shares the generator in prepare_zip_mod.rs with the wasm module.
+//! The inputs come from a json config file and/or from flags. The flags override the config file.
+
+use rust_wasm_helper_for_pwa::error_mod::HelperError;
+use rust_wasm_helper_for_pwa::png_optimization_mod::png_report_summary;
+use rust_wasm_helper_for_pwa::prepare_zip_mod::*;
+
+const HELP: &str = r#"rust_wasm_helper_for_pwa - creates a minimal PWA from a png icon
+
+Usage:
+  rust_wasm_helper_for_pwa [options] --icon <icon.png> --zip <pwa_minimal_files.zip>
+  rust_wasm_helper_for_pwa [options] --icon <icon.png> --extract <folder>
+
+Options:
+  --config <pwa.json>      json file with the same fields as the web form, for example
+                           {"pwa_short_name": "Example", "pwa_folder": "example", "icon": "icon.png"}
+  --short-name <text>      pwa_short_name
+  --name <text>            pwa_name
+  --description <text>     pwa_description
+  --folder <text>          pwa_folder, the files are inside this folder
+  --icon <file.png>        the source png at least 512x512, or a svg
+  --small-icon <file.png>  a hand-tuned square source for the small sizes (e.g. 16, 32, 48),
+                           repeat it for more files. Every size uses the nearest larger source.
+  --zip <file.zip>         write the zip file
+  --extract <folder>       write the files extracted in this folder
+  --help                   this help
+"#;
+
+/// the config file has all the fields of PwaData and the paths of the input and output files
+#[derive(serde::Deserialize, Default)]
+#[serde(default)]
+struct CliConfig {
+    #[serde(flatten)]
+    pwa_data: PwaData,
+    icon: Option<String>,
+    small_icons: Vec<String>,
+    zip: Option<String>,
+    extract: Option<String>,
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+/// parse the arguments, generate and write the files
+fn run() -> Result<(), HelperError> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        print!("{}", HELP);
+        return Ok(());
+    }
+    let config = parse_args(&args)?;
+
+    let icon = config
+        .icon
+        .ok_or_else(|| HelperError::Input("The source png is missing. Use --icon <file.png>.".to_string()))?;
+    if config.zip.is_none() && config.extract.is_none() {
+        return Err(HelperError::Input(
+            "The output is missing. Use --zip <file.zip> or --extract <folder>.".to_string(),
+        ));
+    }
+    let image_bytes = std::fs::read(&icon)?;
+    let small_sources_bytes = config
+        .small_icons
+        .iter()
+        .map(std::fs::read)
+        .collect::<Result<Vec<Vec<u8>>, std::io::Error>>()?;
+    let generated = generate_pwa_zip(&config.pwa_data, &image_bytes, &small_sources_bytes, date_time_now())?;
+    for warning in generated.warnings.iter() {
+        eprintln!("Warning: {}", warning);
+    }
+    if !generated.png_sizes.is_empty() {
+        println!("PNG optimization: {}", png_report_summary(&generated.png_sizes));
+    }
+
+    if let Some(zip_path) = config.zip {
+        std::fs::write(&zip_path, &generated.zip)?;
+        println!("Written {}", zip_path);
+    }
+    if let Some(folder) = config.extract {
+        extract_zip(&generated.zip, std::path::Path::new(&folder))?;
+        println!("Extracted into {}", folder);
+    }
+    Ok(())
+}
+
+/// first read the config file, then the flags override the values
+fn parse_args(args: &[String]) -> Result<CliConfig, HelperError> {
+    let mut config = match args.iter().position(|arg| arg == "--config") {
+        Some(pos) => {
+            let path = flag_value(args, pos)?;
+            let text = std::fs::read_to_string(path)?;
+            serde_json::from_str(&text).map_err(|err| HelperError::Input(format!("Config file {} is not valid json: {}", path, err)))?
+        }
+        None => CliConfig::default(),
+    };
+    let mut pos = 0;
+    while pos < args.len() {
+        let value = flag_value(args, pos)?.to_string();
+        match args[pos].as_str() {
+            "--config" => {}
+            "--short-name" => config.pwa_data.pwa_short_name = value,
+            "--name" => config.pwa_data.pwa_name = value,
+            "--description" => config.pwa_data.pwa_description = value,
+            "--folder" => config.pwa_data.pwa_folder = value,
+            "--icon" => config.icon = Some(value),
+            "--small-icon" => config.small_icons.push(value),
+            "--zip" => config.zip = Some(value),
+            "--extract" => config.extract = Some(value),
+            flag => return Err(HelperError::Input(format!("Unknown argument `{}`. Use --help.", flag))),
+        }
+        pos += 2;
+    }
+    // return
+    Ok(config)
+}
+
+/// all the flags have a value after them
+fn flag_value(args: &[String], pos: usize) -> Result<&str, HelperError> {
+    args.get(pos + 1)
+        .map(|value| value.as_str())
+        .ok_or_else(|| HelperError::Input(format!("The argument {} needs a value.", args[pos])))
+}
+
+/// extract the generated zip into a folder, the same as unzipping it manually
+fn extract_zip(zip_bytes: &[u8], folder: &std::path::Path) -> Result<(), HelperError> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(zip_bytes))?;
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let path = match file.enclosed_name() {
+            Some(path) => folder.join(path),
+            None => return Err(HelperError::Input(format!("Invalid file name in zip: {}", file.name()))),
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut out = std::fs::File::create(&path)?;
+        std::io::copy(&mut file, &mut out)?;
+    }
+    Ok(())
+}
+
+/// now in UTC as zip DateTime
+fn date_time_now() -> zip::DateTime {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    date_time_from_unix_secs(secs)
+}
+
+/// the seconds since 1970 in UTC as zip DateTime, std has no calendar so the days are converted here.
+/// The zip DateTime starts in 1980, the older dates are 1980-01-01.
+fn date_time_from_unix_secs(secs: u64) -> zip::DateTime {
+    let (days, secs_of_day) = ((secs / 86_400) as i64, secs % 86_400);
+    // civil from days, the algorithm from http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    // return
+    zip::DateTime::from_date_and_time(
+        year as u16,
+        month as u8,
+        day as u8,
+        (secs_of_day / 3600) as u8,
+        (secs_of_day % 3600 / 60) as u8,
+        (secs_of_day % 60) as u8,
+    )
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date_time_tuple(date_time: zip::DateTime) -> (u16, u8, u8, u8, u8, u8) {
+        (
+            date_time.year(),
+            date_time.month(),
+            date_time.day(),
+            date_time.hour(),
+            date_time.minute(),
+            date_time.second(),
+        )
+    }
+
+    #[test]
+    fn date_time_from_unix_secs_known_dates() {
+        assert_eq!(date_time_tuple(date_time_from_unix_secs(1_700_000_000)), (2023, 11, 14, 22, 13, 20));
+        // leap days
+        assert_eq!(date_time_tuple(date_time_from_unix_secs(951_782_400)), (2000, 2, 29, 0, 0, 0));
+        assert_eq!(date_time_tuple(date_time_from_unix_secs(1_709_251_199)), (2024, 2, 29, 23, 59, 59));
+        // 2100 is not a leap year
+        assert_eq!(date_time_tuple(date_time_from_unix_secs(4_107_542_399)), (2100, 2, 28, 23, 59, 59));
+    }
+
+    #[test]
+    fn date_time_before_1980_is_the_zip_default() {
+        assert_eq!(date_time_tuple(date_time_from_unix_secs(0)), (1980, 1, 1, 0, 0, 0));
+    }
+}
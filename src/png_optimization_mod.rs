@@ -0,0 +1,280 @@
+//! png_optimization_mod.rs
+//! The default png encoder of the crate `image` writes big files. Lighthouse flags the oversized icons.
+//! The optimization reduces the color type (rgba, rgb or palette with the smallest bit depth),
+//! tries all the png filters and compresses with the best deflate level.
+//! The encoder writes only the critical chunks, so there is no metadata in the optimized png.
+//! Pure Rust, no javascript objects here.
+
+use crate::error_mod::HelperError;
+use std::collections::HashMap;
+
+/// the filters tried for every png with fast compression, the smallest result wins
+const PNG_FILTERS: &[png::FilterType] = &[
+    png::FilterType::NoFilter,
+    png::FilterType::Sub,
+    png::FilterType::Up,
+    png::FilterType::Avg,
+    png::FilterType::Paeth,
+];
+
+/// the png size before and after the optimization, for the report
+#[derive(Clone, Debug)]
+pub struct PngSize {
+    /// the path relative to the pwa folder
+    pub path: String,
+    pub bytes_before: usize,
+    pub bytes_after: usize,
+}
+
+/// the color type and the raw rows for the png encoder
+struct ReducedImage {
+    color_type: png::ColorType,
+    bit_depth: png::BitDepth,
+    palette: Option<Vec<u8>>,
+    trns: Option<Vec<u8>>,
+    data: Vec<u8>,
+}
+
+/// encode the image to the smallest png this encoder can make
+pub fn optimize_png(img: &image::DynamicImage) -> Result<Vec<u8>, HelperError> {
+    let rgba = img.to_rgba8();
+    let reduced = reduce_color_type(&rgba);
+    // the best compression is slow, so the filters are compared with the fast compression
+    let mut best_filter = png::FilterType::NoFilter;
+    let mut best_len = usize::MAX;
+    for filter in PNG_FILTERS.iter() {
+        let png = encode_reduced(&reduced, rgba.width(), rgba.height(), *filter, png::Compression::Fast)?;
+        if png.len() < best_len {
+            best_len = png.len();
+            best_filter = *filter;
+        }
+    }
+    encode_reduced(&reduced, rgba.width(), rgba.height(), best_filter, png::Compression::Best)
+}
+
+/// palette if there are max 256 colors, rgb if all pixels are opaque, else rgba
+fn reduce_color_type(rgba: &image::RgbaImage) -> ReducedImage {
+    if let Some(reduced) = reduce_to_palette(rgba) {
+        return reduced;
+    }
+    if rgba.pixels().all(|pixel| pixel[3] == 255) {
+        let data = rgba.pixels().flat_map(|pixel| [pixel[0], pixel[1], pixel[2]]).collect();
+        return ReducedImage {
+            color_type: png::ColorType::RGB,
+            bit_depth: png::BitDepth::Eight,
+            palette: None,
+            trns: None,
+            data,
+        };
+    }
+    // return
+    ReducedImage {
+        color_type: png::ColorType::RGBA,
+        bit_depth: png::BitDepth::Eight,
+        palette: None,
+        trns: None,
+        data: rgba.as_raw().clone(),
+    }
+}
+
+/// the palette has the transparent colors first, so the tRNS chunk is short.
+/// The indexes are packed in the smallest bit depth: 1, 2, 4 or 8.
+fn reduce_to_palette(rgba: &image::RgbaImage) -> Option<ReducedImage> {
+    let mut colors: Vec<[u8; 4]> = vec![];
+    let mut index_of: HashMap<[u8; 4], u8> = HashMap::new();
+    for pixel in rgba.pixels() {
+        if let std::collections::hash_map::Entry::Vacant(entry) = index_of.entry(pixel.0) {
+            if colors.len() == 256 {
+                return None;
+            }
+            entry.insert(0);
+            colors.push(pixel.0);
+        }
+    }
+    colors.sort_by_key(|color| color[3] == 255);
+    for (index, color) in colors.iter().enumerate() {
+        index_of.insert(*color, index as u8);
+    }
+    let bits: u32 = match colors.len() {
+        0..=2 => 1,
+        3..=4 => 2,
+        5..=16 => 4,
+        _ => 8,
+    };
+    let bit_depth = match bits {
+        1 => png::BitDepth::One,
+        2 => png::BitDepth::Two,
+        4 => png::BitDepth::Four,
+        _ => png::BitDepth::Eight,
+    };
+    // every row starts at a byte boundary
+    let row_bytes = (rgba.width() * bits).div_ceil(8) as usize;
+    let mut data = vec![0u8; row_bytes * rgba.height() as usize];
+    for (y, row) in rgba.rows().enumerate() {
+        for (x, pixel) in row.enumerate() {
+            let bit_position = x * bits as usize;
+            let shift = 8 - bits as usize - bit_position % 8;
+            data[y * row_bytes + bit_position / 8] |= index_of[&pixel.0] << shift;
+        }
+    }
+    let palette = colors.iter().flat_map(|color| [color[0], color[1], color[2]]).collect();
+    let transparent_count = colors.iter().filter(|color| color[3] != 255).count();
+    let trns = if transparent_count > 0 {
+        Some(colors[..transparent_count].iter().map(|color| color[3]).collect())
+    } else {
+        None
+    };
+    // return
+    Some(ReducedImage {
+        color_type: png::ColorType::Indexed,
+        bit_depth,
+        palette: Some(palette),
+        trns,
+        data,
+    })
+}
+
+/// encode with the filter and the deflate level, only the critical chunks are written
+fn encode_reduced(
+    reduced: &ReducedImage,
+    width: u32,
+    height: u32,
+    filter: png::FilterType,
+    compression: png::Compression,
+) -> Result<Vec<u8>, HelperError> {
+    let mut vec_u8: Vec<u8> = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut vec_u8, width, height);
+        encoder.set_color(reduced.color_type);
+        encoder.set_depth(reduced.bit_depth);
+        if let Some(palette) = &reduced.palette {
+            encoder.set_palette(palette.clone());
+        }
+        if let Some(trns) = &reduced.trns {
+            encoder.set_trns(trns.clone());
+        }
+        encoder.set_compression(compression);
+        encoder.set_filter(filter);
+        let mut writer = encoder.write_header().map_err(|err| HelperError::ImageEncode(err.to_string()))?;
+        writer
+            .write_image_data(&reduced.data)
+            .map_err(|err| HelperError::ImageEncode(err.to_string()))?;
+    }
+    // return
+    Ok(vec_u8)
+}
+
+/// the total bytes before and after, for example: `52 png files: 1234567 bytes -> 456789 bytes (-63%)`
+pub fn png_report_summary(png_sizes: &[PngSize]) -> String {
+    let bytes_before: usize = png_sizes.iter().map(|png_size| png_size.bytes_before).sum();
+    let bytes_after: usize = png_sizes.iter().map(|png_size| png_size.bytes_after).sum();
+    // the optimized png is never bigger, no files is 0%
+    let percent = 100 - (bytes_after * 100).checked_div(bytes_before).unwrap_or(100);
+    format!(
+        "{} png files: {} bytes -> {} bytes (-{}%)",
+        png_sizes.len(),
+        bytes_before,
+        bytes_after,
+        percent
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// an image with `count` different colors, also in a single column, every 4th color is transparent and the next translucent
+    fn image_with_colors(width: u32, height: u32, count: u32) -> image::RgbaImage {
+        image::RgbaImage::from_fn(width, height, |x, y| {
+            let index = (x * 3 + y * 7) % count;
+            let alpha = match index % 4 {
+                0 => 0,
+                1 => 128,
+                _ => 255,
+            };
+            image::Rgba([(index * 13) as u8, (index * 29) as u8, (index * 71) as u8, alpha])
+        })
+    }
+
+    fn decode(png: &[u8]) -> image::RgbaImage {
+        image::load_from_memory(png).unwrap().to_rgba8()
+    }
+
+    #[test]
+    fn palette_packs_the_indexes_msb_first() {
+        let black = image::Rgba([0, 0, 0, 255]);
+        let white = image::Rgba([255, 255, 255, 255]);
+        let rgba = image::RgbaImage::from_fn(3, 2, |x, y| if (x + y) % 2 == 0 { black } else { white });
+        let reduced = reduce_to_palette(&rgba).unwrap();
+        assert_eq!(reduced.bit_depth, png::BitDepth::One);
+        // every row starts at a byte boundary: 010 and 101
+        assert_eq!(reduced.data, vec![0b0100_0000, 0b1010_0000]);
+        assert_eq!(reduced.palette, Some(vec![0, 0, 0, 255, 255, 255]));
+        assert_eq!(reduced.trns, None);
+    }
+
+    #[test]
+    fn palette_round_trip_in_every_bit_depth() {
+        for (count, bit_depth, bits) in [
+            (2, png::BitDepth::One, 1),
+            (4, png::BitDepth::Two, 2),
+            (16, png::BitDepth::Four, 4),
+            (40, png::BitDepth::Eight, 8),
+        ]
+        .iter()
+        {
+            for width in [1, 3, 5, 7, 13].iter() {
+                let rgba = image_with_colors(*width, 64, *count);
+                let reduced = reduce_to_palette(&rgba).unwrap();
+                assert_eq!(reduced.bit_depth, *bit_depth, "{} colors", count);
+                assert_eq!(reduced.data.len(), (*width * bits).div_ceil(8) as usize * 64);
+                let png = encode_reduced(&reduced, *width, 64, png::FilterType::Paeth, png::Compression::Fast).unwrap();
+                assert_eq!(decode(&png), rgba, "{} colors, width {}", count, width);
+            }
+        }
+    }
+
+    #[test]
+    fn palette_has_the_transparent_colors_first() {
+        let rgba = image_with_colors(8, 8, 16);
+        let reduced = reduce_to_palette(&rgba).unwrap();
+        let trns = reduced.trns.unwrap();
+        assert_eq!(trns.len(), 8);
+        assert!(trns.iter().all(|alpha| *alpha != 255));
+    }
+
+    #[test]
+    fn more_than_256_colors_is_not_a_palette() {
+        let rgba = image::RgbaImage::from_fn(17, 17, |x, y| image::Rgba([x as u8, y as u8, 0, 255]));
+        assert!(reduce_to_palette(&rgba).is_none());
+        let reduced = reduce_color_type(&rgba);
+        assert_eq!(reduced.color_type, png::ColorType::RGB);
+    }
+
+    #[test]
+    fn optimized_png_has_the_same_pixels() {
+        let rgba = image::RgbaImage::from_fn(33, 21, |x, y| {
+            image::Rgba([x as u8 * 7, y as u8 * 11, (x * y) as u8, (x * 8) as u8])
+        });
+        let png = optimize_png(&image::DynamicImage::ImageRgba8(rgba.clone())).unwrap();
+        assert_eq!(decode(&png), rgba);
+    }
+
+    #[test]
+    fn report_summary_in_percent() {
+        let png_sizes = vec![
+            PngSize {
+                path: "icons/a.png".to_string(),
+                bytes_before: 300,
+                bytes_after: 100,
+            },
+            PngSize {
+                path: "icons/b.png".to_string(),
+                bytes_before: 100,
+                bytes_after: 100,
+            },
+        ];
+        assert_eq!(png_report_summary(&png_sizes), "2 png files: 400 bytes -> 200 bytes (-50%)");
+        assert_eq!(png_report_summary(&[]), "0 png files: 0 bytes -> 0 bytes (-0%)");
+    }
+}
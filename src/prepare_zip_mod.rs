@@ -1,387 +1,552 @@
 //! prepare_zip_mod.rs should not use any javascript objects. Pure Rust.
 //! All the javascript objects, functions and conversion should be in web_sys_mod.rs.
-
-use unwrap::unwrap;
-
-use crate::web_sys_mod::*;
+//! The generator takes PwaData and the image bytes and returns the zip bytes.
+//! It is platform-free, so it can be called from native Rust, for example from a build script.
+
+use crate::apple_splash_mod::*;
+use crate::error_mod::HelperError;
+use crate::icon_set_mod::*;
+use crate::image_processing_mod::*;
+use crate::image_variant_mod::*;
+use crate::png_optimization_mod::*;
+use crate::service_worker_mod::*;
+use crate::source_set_mod::SourceSet;
+use crate::source_validation_mod::*;
+use crate::utils_mod::{content_hash, html_encode};
+use crate::web_app_manifest_mod::*;
+
+/// all the data typed by the user, the input of the generator
+/// The CLI reads it also from a json config file, the missing fields get the default values.
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(default)]
 pub struct PwaData {
     pub pwa_short_name: String,
     pub pwa_name: String,
     pub pwa_description: String,
     pub pwa_folder: String,
+    /// the icon set in the text format, one entry per line
+    pub pwa_icon_set: String,
+    /// the resampling filter of the resize: nearest, triangle, catmull-rom, gaussian, lanczos3, pixel-art
+    pub pwa_resize_filter: String,
+    /// resize in linear light instead of sRGB
+    pub pwa_linear_light: bool,
+    /// the unsharp mask strength from 0 to 3 for the sizes below 64px, 0 is off
+    pub pwa_sharpen: String,
+    /// palette reduction, the best png filter and max compression for every png
+    pub pwa_png_optimization: bool,
+    /// lossless webp variants of the manifest icons
+    pub pwa_webp: bool,
+    /// lossy avif variants of the manifest icons, it needs the cargo feature avif
+    pub pwa_avif: bool,
+    /// the crop rectangle `x y width height` in fractions of the source, empty is no crop
+    pub pwa_crop_rect: String,
+    /// a non-square source is padded or cropped to square, or abort on any issue: pad, crop or abort
+    pub pwa_source_fix: String,
+    /// #rrggbb or transparent
+    pub pwa_maskable_background: String,
+    /// compression of png and ico in the zip: stored or deflate
+    pub pwa_image_compression: String,
+    // the other fields of the web app manifest
+    pub pwa_id: String,
+    pub pwa_start_url: String,
+    pub pwa_scope: String,
+    pub pwa_lang: String,
+    pub pwa_dir: String,
+    pub pwa_display: String,
+    /// comma separated list
+    pub pwa_display_override: String,
+    pub pwa_orientation: String,
+    pub pwa_background_color: String,
+    pub pwa_theme_color: String,
+    /// comma separated list
+    pub pwa_categories: String,
+    pub pwa_iarc_rating_id: String,
+    /// json array
+    pub pwa_shortcuts: String,
+    /// json array
+    pub pwa_screenshots: String,
+    /// json array
+    pub pwa_related_applications: String,
+    pub pwa_prefer_related_applications: bool,
+    /// the caching strategy per URL pattern, one rule per line
+    pub pwa_caching_rules: String,
+    /// extra paths to precache, that are not in the zip, one per line
+    pub pwa_precache_extra: String,
+    /// generate offline.html as the fallback for navigations when offline
+    pub pwa_offline_page: bool,
+    /// CACHE_NAME of the service worker from the date or from the content hash: date or content-hash
+    pub pwa_cache_version: String,
+    /// splash images for iOS on the manifest background_color
+    pub pwa_apple_splash: bool,
+    /// install button with beforeinstallprompt and the iOS instructions in index.html
+    pub pwa_install_button: bool,
+    /// when a new version is installed: reload immediately or show the update banner: reload or banner
+    pub pwa_update_flow: String,
+    /// the text of the update banner
+    pub pwa_update_banner_text: String,
 }
 
-/// The app starts with this function
-pub fn start_function() {
-    // Initialize debugging for when/if something goes wrong.
-    console_error_panic_hook::set_once();
-    // write the app version just for debug purposes
-    debug_write(&format!(
-        "rust_wasm_helper_for_pwa v{}",
-        env!("CARGO_PKG_VERSION")
-    ));
-    // set the window initial size
-    resize_window(800, 600);
-    // load from local storage
-    let data = load_all_from_local_storage();
-    // inject html into DOM
-    inject_htm_into_dom(&data);
-    // prepare events that read local file, pass the function to execute
-    add_listener_on_file_change_to_read_single_file("file_input", &on_file_change);
+impl Default for PwaData {
+    /// the values used when nothing is saved in local_storage yet
+    fn default() -> Self {
+        PwaData {
+            pwa_short_name: "pwa_short_name".to_string(),
+            pwa_name: "pwa_name".to_string(),
+            pwa_description: "pwa_description".to_string(),
+            pwa_folder: "pwa_folder".to_string(),
+            pwa_icon_set: DEFAULT_ICON_SET.to_string(),
+            pwa_resize_filter: "lanczos3".to_string(),
+            pwa_linear_light: false,
+            pwa_sharpen: "0".to_string(),
+            pwa_png_optimization: true,
+            pwa_webp: false,
+            pwa_avif: false,
+            pwa_crop_rect: String::new(),
+            pwa_source_fix: "pad".to_string(),
+            pwa_maskable_background: "#000000".to_string(),
+            pwa_image_compression: "stored".to_string(),
+            pwa_id: String::new(),
+            pwa_start_url: String::new(),
+            pwa_scope: String::new(),
+            pwa_lang: "en".to_string(),
+            pwa_dir: String::new(),
+            pwa_display: "standalone".to_string(),
+            pwa_display_override: String::new(),
+            pwa_orientation: "portrait".to_string(),
+            pwa_background_color: "#000000".to_string(),
+            pwa_theme_color: "#000000".to_string(),
+            pwa_categories: String::new(),
+            pwa_iarc_rating_id: String::new(),
+            pwa_shortcuts: String::new(),
+            pwa_screenshots: String::new(),
+            pwa_related_applications: String::new(),
+            pwa_prefer_related_applications: false,
+            pwa_caching_rules: DEFAULT_CACHING_RULES.to_string(),
+            pwa_precache_extra: String::new(),
+            pwa_offline_page: false,
+            pwa_cache_version: "date".to_string(),
+            pwa_apple_splash: false,
+            pwa_install_button: false,
+            pwa_update_flow: "reload".to_string(),
+            pwa_update_banner_text: "New version available — reload".to_string(),
+        }
+    }
 }
 
-/// load all from local storage
-pub fn load_all_from_local_storage() -> PwaData {
-    let data = PwaData {
-        pwa_short_name: load_string_from_local_storage("pwa_short_name", "pwa_short_name"),
-        pwa_name: load_string_from_local_storage("pwa_name", "pwa_name"),
-        pwa_description: load_string_from_local_storage("pwa_description", "pwa_description"),
-        pwa_folder: load_string_from_local_storage("pwa_folder", "pwa_folder"),
-    };
-    // return
-    data
+/// one generated png icon
+pub struct GeneratedIcon {
+    pub entry: IconEntry,
+    pub png: Vec<u8>,
 }
 
-/// inject html into dom
-pub fn inject_htm_into_dom(pwa_data: &PwaData) {
-    // rust has `Raw string literals` that are great!
-    // just add r# before and # after the start and end double quotes.
-    let html = format!(
-        r##"
-        <h2>Helper for PWA</h2>
-		<p>Creates a minimal working PWA that can be than copied to your project.
-		All the processing is done inside your browser with wasm.
-        No data is transferred over the net.</p>
-		<p>First enter this basic info:</p>
-		<div class="button-wrap">
-            <label for="pwa_short_name">PWA short name:</label>  
-            <input style="width:20%;" type="text" id="pwa_short_name" value="{}"/>
-        </div>
-		<div class="button-wrap">
-            <label for="pwa_name">PWA name:</label>  
-            <input style="width:40%;"  type="text" id="pwa_name" value="{}"/>
-        </div>
-		<div class="button-wrap">
-            <label for="pwa_description">PWA description:</label>  
-            <input style="width:80%;" type="text" id="pwa_description" value="{}"/>
-        </div>
-        <div class="button-wrap">
-            <label for="pwa_folder">PWA folder name:</label>  
-            <input style="width:40%;" type="text" id="pwa_folder" value="{}"/>
-        </div>
-        <p>To create a bunch of png of different sizes,
-		select the png file at least 512x512 or bigger.</p>
-        
-        <!--tricky div+label+css to change Input file appearance -->
-        <div class="button-wrap">
-            <label class="button" for="file_input">Select File</label>
-            <!--only one single png file. No "multiple". The event listeners are added in Rust code.-->  
-            <input type="file" id="file_input" accept="image/png"/>
-        </div>
-        "##,
-        html_encode(&pwa_data.pwa_short_name),
-        html_encode(&pwa_data.pwa_name),
-        html_encode(&pwa_data.pwa_description),
-        html_encode(&pwa_data.pwa_folder)
-    );
-
-    set_inner_html("div_for_wasm_html_injecting", &html);
+/// the result of the generator: the zip and the icons for a preview
+pub struct GeneratedPwa {
+    pub zip: Vec<u8>,
+    pub icons: Vec<GeneratedIcon>,
+    /// the issues of the source image that did not abort the generation
+    pub warnings: Vec<String>,
+    /// the png sizes before and after the optimization, empty if it is off
+    pub png_sizes: Vec<PngSize>,
 }
 
-/// on file change code that is not boilerplate
-pub fn on_file_change(vec: Vec<u8>) {
-    // get date time now
-    let now = date_time_now();
-
-    // save Input Text elements to local storage
-    let pwa_data = read_input_elements_and_save_to_local_storage();
-    let img = decode_png(vec);
-    let mut buf = &mut vec![0u8; 2_097_152];
-    let mut zip = create_new_zip(&mut buf);
+/// generate all the files and add them to zip
+pub fn generate_pwa_zip(
+    pwa_data: &PwaData,
+    image_bytes: &[u8],
+    small_sources_bytes: &[Vec<u8>],
+    now: zip::DateTime,
+) -> Result<GeneratedPwa, HelperError> {
+    let icon_set = IconSet::parse(&pwa_data.pwa_icon_set)?;
+    let caching_rules = CachingRules::parse(&pwa_data.pwa_caching_rules)?;
+    let mut manifest = web_app_manifest(pwa_data, &icon_set)?;
+    let maskable_background = parse_background(&pwa_data.pwa_maskable_background)?;
+    let precache_extra = parse_precache_extra(&pwa_data.pwa_precache_extra)?;
+    let source_fix = SourceFix::parse(&pwa_data.pwa_source_fix)?;
+    let mut source = decode_source_image(image_bytes)?;
+    // the crop is applied before the validation and before every resize
+    if let Some(crop_rect) = CropRect::parse(&pwa_data.pwa_crop_rect)? {
+        source = source.crop(&crop_rect)?;
+    }
+    let (source, warnings) = validate_source(source, image_bytes, source_fix)?;
+    // the hand-tuned small sources are used for the small sizes
+    let small_sources = small_sources_bytes
+        .iter()
+        .map(|bytes| decode_source_image(bytes))
+        .collect::<Result<Vec<SourceImage>, HelperError>>()?;
+    let sources = SourceSet::new(source, small_sources)?;
+    let mut zip = PwaZip::new(&pwa_data.pwa_folder, now, pwa_data.pwa_png_optimization);
 
     // favicon.ico with 16, 32 and 48 icons
-    encode_to_favicon_ico_and_add_to_zip(&mut zip, &img, &now, &pwa_data.pwa_folder);
+    let image_compression = image_compression_method(&pwa_data.pwa_image_compression);
+    let resize_options = ResizeOptions {
+        filter: ResizeFilter::parse(&pwa_data.pwa_resize_filter)?,
+        linear_light: pwa_data.pwa_linear_light,
+        sharpen: parse_sharpen_strength(&pwa_data.pwa_sharpen)?,
+    };
+    encode_to_favicon_ico_and_add_to_zip(&mut zip, &sources, resize_options, image_compression)?;
 
     // png with various sizes for: favicon png, pwa Android and pwa iOS
-    // 32, 72, 96, 120, 128, 144, 152, 167, 180, 192, 196, 512
-    resize_img_and_add_to_zip(
-        &mut zip,
-        &img,
-        32,
-        "icon-032.png",
-        &now,
-        &pwa_data.pwa_folder,
-    );
-    resize_img_and_add_to_zip(
-        &mut zip,
-        &img,
-        72,
-        "icon-072.png",
-        &now,
-        &pwa_data.pwa_folder,
-    );
-    resize_img_and_add_to_zip(
-        &mut zip,
-        &img,
-        96,
-        "icon-096.png",
-        &now,
-        &pwa_data.pwa_folder,
-    );
-    resize_img_and_add_to_zip(
-        &mut zip,
-        &img,
-        120,
-        "icon-120.png",
-        &now,
-        &pwa_data.pwa_folder,
-    );
-    resize_img_and_add_to_zip(
-        &mut zip,
-        &img,
-        128,
-        "icon-128.png",
-        &now,
-        &pwa_data.pwa_folder,
-    );
-    resize_img_and_add_to_zip(
-        &mut zip,
-        &img,
-        144,
-        "icon-144.png",
-        &now,
-        &pwa_data.pwa_folder,
-    );
-    resize_img_and_add_to_zip(
-        &mut zip,
-        &img,
-        152,
-        "icon-152.png",
-        &now,
-        &pwa_data.pwa_folder,
-    );
-    resize_img_and_add_to_zip(
-        &mut zip,
-        &img,
-        167,
-        "icon-167.png",
-        &now,
-        &pwa_data.pwa_folder,
-    );
-    resize_img_and_add_to_zip(
-        &mut zip,
-        &img,
-        180,
-        "icon-180.png",
-        &now,
-        &pwa_data.pwa_folder,
-    );
-    resize_img_and_add_to_zip(
-        &mut zip,
-        &img,
-        192,
-        "icon-192.png",
-        &now,
-        &pwa_data.pwa_folder,
-    );
-    resize_img_and_add_to_zip(
-        &mut zip,
-        &img,
-        196,
-        "icon-196.png",
-        &now,
-        &pwa_data.pwa_folder,
-    );
-    resize_img_and_add_to_zip(
-        &mut zip,
-        &img,
-        512,
-        "icon-512.png",
-        &now,
-        &pwa_data.pwa_folder,
-    );
-
-    // maskable icon 192
-    resize_img_and_add_to_zip(
-        &mut zip,
-        &img,
-        192,
-        "icon-maskable.png",
-        &now,
-        &pwa_data.pwa_folder,
-    );
+    // maskable icons have the artwork inside the safe zone
+    // the webp and avif variants only for the manifest, the other platforms need png
+    let variants = ImageVariant::chosen(pwa_data.pwa_webp, pwa_data.pwa_avif)?;
+    let mut icons = vec![];
+    for entry in icon_set.entries.iter() {
+        let entry_variants: &[ImageVariant] = if entry.has_platform(IconPlatform::Manifest) {
+            &variants
+        } else {
+            &[]
+        };
+        let png = resize_img_and_add_to_zip(
+            &mut zip,
+            sources.for_size(entry.size),
+            entry,
+            maskable_background,
+            resize_options,
+            entry_variants,
+            image_compression,
+        )?;
+        icons.push(GeneratedIcon { entry: entry.clone(), png });
+    }
+    manifest.icons = manifest_icons_with_variants(std::mem::take(&mut manifest.icons), &variants, |src| zip.has_file(src));
+
+    // splash screens for iOS, they are big and optional
+    let mut head_tags = String::new();
+    if pwa_data.pwa_apple_splash {
+        let splash_background = parse_color("Manifest background_color", &manifest.background_color)?;
+        let splash_images = apple_splash_images();
+        for splash_image in splash_images.iter() {
+            add_splash_image_to_zip(
+                &mut zip,
+                &sources.main,
+                splash_image,
+                splash_background,
+                resize_options,
+                image_compression,
+            )?;
+        }
+        head_tags.push_str(&apple_splash_link_tags(&splash_images));
+    }
+
+    // the svg is a scalable icon for the browsers that support it, with the same crop and square as the png icons
+    if let Some(svg_bytes) = sources.main.to_svg_bytes(image_bytes) {
+        zip.add_text_file(SVG_ICON_SRC, &String::from_utf8_lossy(&svg_bytes))?;
+        manifest.icons.push(ManifestImage {
+            src: SVG_ICON_SRC.to_string(),
+            sizes: Some("any".to_string()),
+            mime_type: Some("image/svg+xml".to_string()),
+            purpose: Some("any".to_string()),
+            ..Default::default()
+        });
+        head_tags.push_str(&format!(
+            r#"            <!-- scalable favicon -->
+            <link rel="icon" type="image/svg+xml" href="{}">
+"#,
+            SVG_ICON_SRC
+        ));
+    }
+
+    // tiles for the pinned sites in Windows, the square tiles are in the icon set
+    if icon_set.has_windows_tiles() {
+        add_wide_tile_to_zip(&mut zip, &sources.main, resize_options, image_compression)?;
+        add_browserconfig_xml_to_zip(&mut zip, &icon_set, &manifest.background_color)?;
+        head_tags.push_str(&format!(
+            r#"            <!-- tiles for Windows -->
+            <meta name="msapplication-TileColor" content="{}">
+            <meta name="msapplication-config" content="browserconfig.xml">
+"#,
+            html_encode(&manifest.background_color)
+        ));
+    }
 
     // text files
-    add_manifest_json_to_zip(
-        &mut zip,
-        &pwa_data.pwa_short_name,
-        &pwa_data.pwa_name,
-        &pwa_data.pwa_folder,
-        &now,
-    );
-    add_index_html_to_zip(
+    add_manifest_json_to_zip(&mut zip, &manifest)?;
+    add_index_html_to_zip(&mut zip, &manifest, &icon_set, &head_tags, pwa_data.pwa_install_button)?;
+    if pwa_data.pwa_install_button {
+        add_install_prompt_js_to_zip(&mut zip)?;
+    }
+    let update_banner_text = match pwa_data.pwa_update_flow.as_str() {
+        "banner" => Some(pwa_data.pwa_update_banner_text.as_str()),
+        _ => None,
+    };
+    add_start_service_worker_js_to_zip(&mut zip, update_banner_text)?;
+    let offline_url = if pwa_data.pwa_offline_page {
+        add_offline_html_to_zip(&mut zip, &manifest, &icon_set)?;
+        Some(OFFLINE_URL)
+    } else {
+        None
+    };
+    // the service worker is the last, it precaches all the other files
+    add_service_worker_js_to_zip(
         &mut zip,
-        &pwa_data.pwa_name,
-        &pwa_data.pwa_description,
-        &now,
-        &pwa_data.pwa_folder,
-    );
-    add_service_worker_js_to_zip(&mut zip, &now, &pwa_data.pwa_folder);
-    add_start_service_worker_js_to_zip(&mut zip, &now, &pwa_data.pwa_folder);
-
-    let url = finish_zip(&mut zip);
-    append_anchor_for_file_url(&url, "pwa_minimal_files.zip");
-    append_final_comment("Extract the zip files to a web site that has https. The files must be inside the defined folder and not on the website root.");
+        &caching_rules,
+        &precache_extra,
+        offline_url,
+        &pwa_data.pwa_cache_version,
+        update_banner_text.is_none(),
+    )?;
+
+    let png_sizes = std::mem::take(&mut zip.png_sizes);
+    let zip = zip.finish()?;
+    // return
+    Ok(GeneratedPwa {
+        zip,
+        icons,
+        warnings,
+        png_sizes,
+    })
 }
 
-/// read input elements and save to local storage
-pub fn read_input_elements_and_save_to_local_storage() -> PwaData {
-    let pwa_data = PwaData {
-        pwa_short_name: get_input_element_value_string_by_id("pwa_short_name"),
-        pwa_name: get_input_element_value_string_by_id("pwa_name"),
-        pwa_folder: get_input_element_value_string_by_id("pwa_folder"),
-        pwa_description: get_input_element_value_string_by_id("pwa_description"),
+/// the web app manifest from the input fields and the icon set
+pub fn web_app_manifest(pwa_data: &PwaData, icon_set: &IconSet) -> Result<WebAppManifest, HelperError> {
+    let manifest = WebAppManifest {
+        id: non_empty(&pwa_data.pwa_id),
+        short_name: pwa_data.pwa_short_name.clone(),
+        name: pwa_data.pwa_name.clone(),
+        description: non_empty(&pwa_data.pwa_description),
+        icons: icon_set.manifest_icons(),
+        start_url: non_empty(&pwa_data.pwa_start_url).unwrap_or(format!("/{}/index.html", pwa_data.pwa_folder)),
+        scope: non_empty(&pwa_data.pwa_scope),
+        lang: non_empty(&pwa_data.pwa_lang),
+        dir: non_empty(&pwa_data.pwa_dir),
+        display: pwa_data.pwa_display.clone(),
+        display_override: split_list(&pwa_data.pwa_display_override),
+        orientation: non_empty(&pwa_data.pwa_orientation),
+        background_color: pwa_data.pwa_background_color.clone(),
+        theme_color: pwa_data.pwa_theme_color.clone(),
+        categories: split_list(&pwa_data.pwa_categories),
+        iarc_rating_id: non_empty(&pwa_data.pwa_iarc_rating_id),
+        shortcuts: parse_json_list("shortcuts", &pwa_data.pwa_shortcuts)?,
+        screenshots: parse_json_list("screenshots", &pwa_data.pwa_screenshots)?,
+        related_applications: parse_json_list("related_applications", &pwa_data.pwa_related_applications)?,
+        prefer_related_applications: pwa_data.pwa_prefer_related_applications,
     };
-    save_to_local_storage("pwa_short_name", &pwa_data.pwa_short_name);
-    save_to_local_storage("pwa_name", &pwa_data.pwa_name);
-    save_to_local_storage("pwa_folder", &pwa_data.pwa_folder);
-    save_to_local_storage("pwa_description", &pwa_data.pwa_description);
     // return
-    pwa_data
+    Ok(manifest)
 }
 
-/// create a zip
-pub fn create_new_zip(buf: &mut [u8]) -> zip::ZipWriter<std::io::Cursor<&mut [u8]>> {
-    debug_write(&format!("create_new_zip"));
-    let w = std::io::Cursor::new(buf);
-    let zip = zip::ZipWriter::new(w);
-    // return
-    zip
+/// the zip in a growable buffer, so the size of the zip is not limited.
+/// It remembers the written files for the precache list of the service worker.
+pub struct PwaZip {
+    zip: zip::ZipWriter<std::io::Cursor<Vec<u8>>>,
+    now: zip::DateTime,
+    pwa_folder: String,
+    /// the files written into the zip and precached by the service worker, the url is relative to the pwa folder
+    pub files: Vec<PrecacheEntry>,
+    /// the files written into the zip, but not precached, the path is relative to the pwa folder
+    pub not_precached_files: Vec<String>,
+    png_optimization: bool,
+    /// the png sizes before and after the optimization
+    pub png_sizes: Vec<PngSize>,
+}
+
+impl PwaZip {
+    pub fn new(pwa_folder: &str, now: zip::DateTime, png_optimization: bool) -> PwaZip {
+        PwaZip {
+            zip: zip::ZipWriter::new(std::io::Cursor::new(Vec::new())),
+            now,
+            pwa_folder: pwa_folder.to_string(),
+            files: vec![],
+            not_precached_files: vec![],
+            png_optimization,
+            png_sizes: vec![],
+        }
+    }
+
+    /// encode the image to png, optimized if it is smaller, and add it to the zip
+    pub fn add_png(
+        &mut self,
+        path: &str,
+        img: image::DynamicImage,
+        compression_method: zip::CompressionMethod,
+        precache: bool,
+    ) -> Result<Vec<u8>, HelperError> {
+        let mut vec_u8 = encode_to_png(&img)?;
+        if self.png_optimization {
+            let bytes_before = vec_u8.len();
+            let optimized = optimize_png(&img)?;
+            if optimized.len() < vec_u8.len() {
+                vec_u8 = optimized;
+            }
+            self.png_sizes.push(PngSize {
+                path: path.to_string(),
+                bytes_before,
+                bytes_after: vec_u8.len(),
+            });
+        }
+        if precache {
+            self.add_file(path, &vec_u8, compression_method)?;
+        } else {
+            self.add_file_not_precached(path, &vec_u8, compression_method)?;
+        }
+        // return the png for the preview
+        Ok(vec_u8)
+    }
+
+    /// add a file inside the pwa folder and into the precache list, the path is relative to the pwa folder
+    pub fn add_file(&mut self, path: &str, content: &[u8], compression_method: zip::CompressionMethod) -> Result<(), HelperError> {
+        self.write_file(path, content, compression_method)?;
+        self.files.push(PrecacheEntry {
+            url: path.to_string(),
+            revision: Some(content_hash(content)),
+        });
+        Ok(())
+    }
+
+    /// text files are always compressed, they shrink a lot
+    pub fn add_text_file(&mut self, path: &str, text: &str) -> Result<(), HelperError> {
+        self.add_file(path, text.as_bytes(), zip::CompressionMethod::Deflated)
+    }
+
+    /// add a file that is not precached, because not every client needs it, for example the splash images for iOS
+    pub fn add_file_not_precached(
+        &mut self,
+        path: &str,
+        content: &[u8],
+        compression_method: zip::CompressionMethod,
+    ) -> Result<(), HelperError> {
+        self.write_file(path, content, compression_method)?;
+        self.not_precached_files.push(path.to_string());
+        Ok(())
+    }
+
+    /// true if the file is in the zip, precached or not
+    pub fn has_file(&self, path: &str) -> bool {
+        self.files.iter().any(|file| file.url == path) || self.not_precached_files.iter().any(|file| file == path)
+    }
+
+    fn write_file(&mut self, path: &str, content: &[u8], compression_method: zip::CompressionMethod) -> Result<(), HelperError> {
+        let options = file_options(compression_method, &self.now);
+        self.zip.start_file(format!("{}/{}", self.pwa_folder, path), options)?;
+        use std::io::Write;
+        self.zip.write_all(content)?;
+        Ok(())
+    }
+
+    /// finish zip and return the bytes
+    pub fn finish(&mut self) -> Result<Vec<u8>, HelperError> {
+        let zip_result: std::io::Cursor<Vec<u8>> = self.zip.finish()?;
+        // return
+        Ok(zip_result.into_inner())
+    }
+}
+
+/// zip file options with the compression method and the modified time
+pub fn file_options(compression_method: zip::CompressionMethod, now: &zip::DateTime) -> zip::write::FileOptions {
+    zip::write::FileOptions::default()
+        .compression_method(compression_method)
+        .last_modified_time(*now)
 }
 
-/// resize img and append anchor
+/// png and ico are already compressed, deflate gains just a little. The user can choose.
+pub fn image_compression_method(pwa_image_compression: &str) -> zip::CompressionMethod {
+    match pwa_image_compression {
+        "deflate" => zip::CompressionMethod::Deflated,
+        _ => zip::CompressionMethod::Stored,
+    }
+}
+
+/// resize img and add to zip, maskable icons get the safe zone padding
 pub fn resize_img_and_add_to_zip(
-    zip: &mut zip::ZipWriter<std::io::Cursor<&mut [u8]>>,
-    img: &image::DynamicImage,
-    img_size: u32,
-    file_name: &str,
-    now: &zip::DateTime,
-    pwa_folder: &str,
-) {
-    debug_write(&format!("resize_img_and_add_to_zip img {}", img_size));
-    let new_img = img.resize(img_size, img_size, image::imageops::FilterType::Lanczos3);
-    let vec_u8 = encode_to_png(new_img);
-
-    let options = zip::write::FileOptions::default()
-        .compression_method(zip::CompressionMethod::Stored)
-        .last_modified_time(*now);
-    let file_name = format!("{}/icons/{}", pwa_folder, file_name);
-    unwrap!(zip.start_file(&file_name, options));
-    use std::io::Write;
-    unwrap!(zip.write(&vec_u8));
+    zip: &mut PwaZip,
+    source: &SourceImage,
+    entry: &IconEntry,
+    maskable_background: Background,
+    resize_options: ResizeOptions,
+    variants: &[ImageVariant],
+    image_compression: zip::CompressionMethod,
+) -> Result<Vec<u8>, HelperError> {
+    let new_img = match entry.purpose {
+        IconPurpose::Any => source.resize(entry.size, resize_options),
+        IconPurpose::Maskable => maskable_icon(source, entry.size, maskable_background, resize_options),
+    };
+    let encoded_variants = variants
+        .iter()
+        .map(|variant| Ok((variant.src(&entry.src()), variant.encode(&new_img)?)))
+        .collect::<Result<Vec<(String, Vec<u8>)>, HelperError>>()?;
+    let png = zip.add_png(&entry.src(), new_img, image_compression, true)?;
+    // a variant bigger than the png is useless.
+    // A browser uses only one format of the icon, so the variants are not precached, the png is.
+    for (src, encoded) in encoded_variants.iter() {
+        if encoded.len() < png.len() {
+            zip.add_file_not_precached(src, encoded, image_compression)?;
+        }
+    }
+    // return the png for the preview
+    Ok(png)
+}
+
+/// splash image for iOS, the source icon centered on the background color
+pub fn add_splash_image_to_zip(
+    zip: &mut PwaZip,
+    source: &SourceImage,
+    splash_image: &SplashImage,
+    background: Background,
+    resize_options: ResizeOptions,
+    image_compression: zip::CompressionMethod,
+) -> Result<(), HelperError> {
+    let new_img = centered_on_background(
+        source,
+        splash_image.width,
+        splash_image.height,
+        splash_image.icon_size(),
+        background,
+        resize_options,
+    );
+    // an opaque background does not need the alpha channel, the big png is smaller without it
+    let new_img = match background {
+        Some(color) if color[3] == 255 => image::DynamicImage::ImageRgb8(new_img.into_rgb8()),
+        _ => new_img,
+    };
+    // iOS reads the splash images only when it starts the app from the home screen, not from the service worker cache
+    zip.add_png(&splash_image.src(), new_img, image_compression, false)?;
+    Ok(())
+}
+
+/// the original svg in the zip
+pub const SVG_ICON_SRC: &str = "icons/icon.svg";
+
+/// the wide Windows tile 310x150, the icon is centered on a transparent background, Windows fills the tile color
+pub fn add_wide_tile_to_zip(
+    zip: &mut PwaZip,
+    source: &SourceImage,
+    resize_options: ResizeOptions,
+    image_compression: zip::CompressionMethod,
+) -> Result<(), HelperError> {
+    let new_img = centered_on_background(source, 310, 150, 150, None, resize_options);
+    zip.add_png(&format!("icons/{}", WIDE_TILE_FILE_NAME), new_img, image_compression, true)?;
+    Ok(())
+}
+
+/// add browserconfig.xml to zip
+pub fn add_browserconfig_xml_to_zip(zip: &mut PwaZip, icon_set: &IconSet, tile_color: &str) -> Result<(), HelperError> {
+    zip.add_text_file("browserconfig.xml", &icon_set.browserconfig_xml(tile_color))
 }
 
 /// add manifest.json to zip
-pub fn add_manifest_json_to_zip(
-    zip: &mut zip::ZipWriter<std::io::Cursor<&mut [u8]>>,
-    pwa_short_name: &str,
-    pwa_name: &str,
-    pwa_folder: &str,
-    now: &zip::DateTime,
-) {
-    debug_write(&format!("add_manifest_json_to_zip"));
-
-    let options = zip::write::FileOptions::default()
-        .compression_method(zip::CompressionMethod::Stored)
-        .last_modified_time(*now);
-    unwrap!(zip.start_file(&format!("{}/manifest.json", pwa_folder), options));
-
-    use std::io::Write;
-    // format! makes {} special characters. Use double {{ and }} for {} literal.
-    unwrap!(zip.write(
-        &format!(
-            r##"{{
-    "short_name": "{}",
-    "name": "{}",
-    "icons": [
-        {{
-            "src": "icons/icon-072.png",
-            "sizes": "72x72",
-            "type": "image/png",
-            "density": "1.5"
-        }},
-        {{
-            "src": "icons/icon-096.png",
-            "sizes": "96x96",
-            "type": "image/png",
-            "density": "2.0"
-        }},
-        {{
-            "src": "icons/icon-128.png",
-            "sizes": "128x128",
-            "type": "image/png",
-            "density": "2.5"
-        }},
-        {{
-            "src": "icons/icon-144.png",
-            "sizes": "144x144",
-            "type": "image/png",
-            "density": "3.0"
-        }},
-        {{
-            "src": "icons/icon-152.png",
-            "sizes": "152x152",
-            "type": "image/png",
-            "density": "3.2"
-        }},
-        {{
-            "src": "icons/icon-192.png",
-            "sizes": "192x192",
-            "type": "image/png",
-            "density": "4.0"
-        }},
-        {{
-            "src": "icons/icon-512.png",
-            "sizes": "512x512",
-            "type": "image/png"            
-        }},
-        {{
-            "src": "icons/icon-maskable.png",
-            "sizes": "192x192",
-            "type": "image/png",
-            "density": "4.0",
-            "purpose": "any maskable"
-        }}
-    ],
-    "start_url": "/{}/index.html",
-    "background_color": "#000000",
-    "display": "standalone",
-    "orientation": "portrait",
-    "theme_color": "#000000"
-}}"##,
-            pwa_short_name, pwa_name, pwa_folder
-        )
-        .as_bytes()
-    ));
+pub fn add_manifest_json_to_zip(zip: &mut PwaZip, manifest: &WebAppManifest) -> Result<(), HelperError> {
+    zip.add_text_file("manifest.json", &manifest.to_json())
 }
 
 /// add index.html to zip
 pub fn add_index_html_to_zip(
-    zip: &mut zip::ZipWriter<std::io::Cursor<&mut [u8]>>,
-    pwa_name: &str,
-    pwa_description: &str,
-    now: &zip::DateTime,
-    pwa_folder: &str,
-) {
-    debug_write(&format!("add_index_html_to_zip"));
-    let options = zip::write::FileOptions::default()
-        .compression_method(zip::CompressionMethod::Stored)
-        .last_modified_time(*now);
-    unwrap!(zip.start_file(&format!("{}/index.html", pwa_folder), options));
+    zip: &mut PwaZip,
+    manifest: &WebAppManifest,
+    icon_set: &IconSet,
+    head_tags: &str,
+    install_button: bool,
+) -> Result<(), HelperError> {
+    let install_html = if install_button {
+        r#"<!-- the button is visible when the browser fires beforeinstallprompt -->
+            <button id="install_button" hidden>Install</button>
+            <p id="ios_install_instructions" hidden>To install this app on iOS, tap the Share button and then "Add to Home Screen".</p>
+            <script src="install_prompt.js"></script>"#
+    } else {
+        "<p>Install me.</p>"
+    };
     let index_html = format!(
         r##"
     <!DOCTYPE html>
-    <html lang="en">
+    <html lang="{}">
         <head>
             <!-- classic header for a web page -->
             <meta http-equiv="Content-type" content="text/html; charset=utf-8" />
@@ -389,25 +554,13 @@ pub fn add_index_html_to_zip(
             <meta name="Description" content="{}">
             <meta name="viewport" content="width = device-width,initial-scale = 1.0" />
                
-            <!-- favicons generic-->
-            <link rel="icon" type="image/png" href="icons/icon-032.png" sizes="32x32">
-            <link rel="icon" type="image/png" href="icons/icon-128.png" sizes="128x128">
-            <link rel="icon" type="image/png" href="icons/icon-192.png" sizes="192x192">
-            <!-- favicons Android -->
-            <link rel="shortcut icon" href="icons/icon-196.png" sizes="196x196">
-            <!-- favicons iOS -->
-            <link rel="apple-touch-icon" href="icons/icon-152.png" sizes="152x152">
-            <link rel="apple-touch-icon" href="icons/icon-167.png" sizes="167x167">
-            <link rel="apple-touch-icon" href="icons/icon-180.png" sizes="180x180">
-
+{}{}
             <!-- Metadata for PWA -->
             <link rel="manifest" href="manifest.json">
             <meta name="mobile-web-app-capable" content="yes">
             <meta name="apple-mobile-web-app-capable" content="yes" />
             <meta name="apple-mobile-web-app-status-bar-style" content="black-translucent" />
-            <meta name="theme-color" content="#000000">
-            <link rel="apple-touch-icon" sizes="120x120" href="icons/icon-120.png">
-            <link rel="apple-touch-icon" sizes="180x180" href="icons/icon-180.png">
+            <meta name="theme-color" content="{}">
         </head>
     <body>
         <!-- a standard service worker is a must for PWA -->
@@ -425,208 +578,316 @@ pub fn add_index_html_to_zip(
 
         <div id="div_content">
             <h1>Hello PWA world!</h1>
-            <p>Install me.</p> 
+            {}
         </div>
     </body>
 </html>
     "##,
-        html_encode(&pwa_name),
-        html_encode(&pwa_description)
+        html_encode(manifest.lang.as_deref().unwrap_or("en")),
+        html_encode(&manifest.name),
+        html_encode(manifest.description.as_deref().unwrap_or("")),
+        icon_set.html_link_tags(),
+        head_tags,
+        html_encode(&manifest.theme_color),
+        install_html
     );
-    use std::io::Write;
-    unwrap!(zip.write(index_html.as_bytes()));
+    zip.add_text_file("index.html", &index_html)
 }
 
-/// add service_worker.js to zip
-pub fn add_service_worker_js_to_zip(
-    zip: &mut zip::ZipWriter<std::io::Cursor<&mut [u8]>>,
-    now: &zip::DateTime,
-    pwa_folder: &str,
-) {
-    debug_write(&format!("add_service_worker_js_to_zip"));
-    let options = zip::write::FileOptions::default()
-        .compression_method(zip::CompressionMethod::Stored)
-        .last_modified_time(*now);
-    unwrap!(zip.start_file(&format!("{}/service_worker.js", pwa_folder), options));
-    use std::io::Write;
-    let version_from_date = format!(
-        "{}.{}{:02}.{}{:02}",
-        now.year(),
-        now.month(),
-        now.day(),
-        now.hour(),
-        now.minute()
+/// add install_prompt.js to zip, the install button and the iOS instructions in index.html
+pub fn add_install_prompt_js_to_zip(zip: &mut PwaZip) -> Result<(), HelperError> {
+    zip.add_text_file("install_prompt.js", INSTALL_PROMPT_JS)
+}
+
+const INSTALL_PROMPT_JS: &str = r##"'use strict';
+
+// the browser fires beforeinstallprompt when the PWA is installable
+let deferredPrompt = null;
+const installButton = document.getElementById('install_button');
+
+window.addEventListener('beforeinstallprompt', event => {
+    // prevent the default mini-infobar, the button shows the prompt
+    event.preventDefault();
+    deferredPrompt = event;
+    installButton.hidden = false;
+});
+
+installButton.addEventListener('click', async () => {
+    if (!deferredPrompt) return;
+    installButton.hidden = true;
+    deferredPrompt.prompt();
+    const choice = await deferredPrompt.userChoice;
+    console.log('Install prompt: ', choice.outcome);
+    // the prompt can be used only once
+    deferredPrompt = null;
+});
+
+window.addEventListener('appinstalled', () => {
+    installButton.hidden = true;
+    deferredPrompt = null;
+});
+
+// iOS Safari has no beforeinstallprompt, the user must use "Add to Home Screen" from the Share menu
+const userAgent = navigator.userAgent;
+const isIos = /iphone|ipad|ipod/i.test(userAgent) || (navigator.platform === 'MacIntel' && navigator.maxTouchPoints > 1);
+const isSafari = /safari/i.test(userAgent) && !/crios|fxios|edgios/i.test(userAgent);
+const isStandalone = window.navigator.standalone === true || window.matchMedia('(display-mode: standalone)').matches;
+if (isIos && isSafari && !isStandalone) {
+    document.getElementById('ios_install_instructions').hidden = false;
+}
+"##;
+
+/// the file name of the offline fallback page
+pub const OFFLINE_URL: &str = "offline.html";
+
+/// add offline.html to zip, a simple page with the name, the icon and the theme color
+pub fn add_offline_html_to_zip(zip: &mut PwaZip, manifest: &WebAppManifest, icon_set: &IconSet) -> Result<(), HelperError> {
+    // the icon nearest to 192, it is shown in a normal size. An icon set without `any` icons has no img.
+    let icon_img = icon_set
+        .entries
+        .iter()
+        .filter(|entry| entry.purpose == IconPurpose::Any)
+        .min_by_key(|entry| (entry.size as i64 - 192).abs())
+        .map(|entry| {
+            format!(
+                "        <img src=\"{}\" alt=\"{}\">\n",
+                html_encode(&entry.src()),
+                html_encode(&manifest.name)
+            )
+        })
+        .unwrap_or_default();
+    let offline_html = format!(
+        r##"<!DOCTYPE html>
+<html lang="{0}">
+    <head>
+        <meta http-equiv="Content-type" content="text/html; charset=utf-8" />
+        <title>{1} - offline</title>
+        <meta name="viewport" content="width = device-width,initial-scale = 1.0" />
+        <meta name="theme-color" content="{2}">
+        <style>
+            body {{
+                font-family: sans-serif;
+                text-align: center;
+                margin: 0;
+                background-color: {3};
+            }}
+            header {{
+                background-color: {2};
+                padding: 16px;
+            }}
+            img {{
+                width: 96px;
+                height: 96px;
+                margin-top: 32px;
+            }}
+        </style>
+    </head>
+    <body>
+        <header></header>
+{4}        <h1>{1}</h1>
+        <p>You are offline. This page will be available when the connection is back.</p>
+        <button onclick="window.location.reload()">Try again</button>
+    </body>
+</html>
+"##,
+        html_encode(manifest.lang.as_deref().unwrap_or("en")),
+        html_encode(&manifest.name),
+        html_encode(&manifest.theme_color),
+        html_encode(&manifest.background_color),
+        icon_img
     );
-    unwrap!(zip.write(
-        format!(r##"
-            'use strict';
-
-            // Incrementing VERSION in CACHE_NAME will kick off the 
-            // install event and force previously cached
-            // resources to be cached again.
-            // but the new service worker will not be activated until all 
-            // tabs with this webapp are closed.
-            
-            const CACHE_NAME = '{}';
-            
-            self.addEventListener('install', event => {{
-                console.log('event install ', CACHE_NAME);
-                // the ugly trick of avoiding the waiting phase
-                self.skipWaiting();
-            
-                event.waitUntil(
-                    caches.open(CACHE_NAME).then(function (cache) {{
-                        return cache.addAll(
-                            [
-                                'index.html',
-                            ]
-                        );
-                    }})
-                );
-            }});
-            
-            self.addEventListener('activate', event => {{
-                console.log('event activate');
-                // Delete all caches that aren't CACHE_NAME.
-                event.waitUntil(
-                    caches.keys().then(cacheNames => {{
-                        return Promise.all(
-                            cacheNames.map(cacheName => {{
-                                if (CACHE_NAME.indexOf(cacheName) === -1) {{
-                                    // If this cache name isn't right, then delete it.
-                                    console.log('Deleting out of date cache:', cacheName);
-                                    return caches.delete(cacheName);
-                                }}
-                            }})
-                        );
-                    }})
-                );
-            }});
-            
-            self.addEventListener('fetch', event => {{
-                // console.log('event fetch');
-                // Let the browser do its default thing
-                // for non-GET requests.
-                if (event.request.method != 'GET') return;
-            
-                // Prevent the default, and handle the request ourselves.
-                event.respondWith(async function () {{
-                    // Try to get the response from a cache.
-                    const cache = await caches.open(CACHE_NAME);
-                    const cachedResponse = await cache.match(event.request);
-            
-                    if (cachedResponse) {{
-                        // console.log('from cache');
-                        // If we found a match in the cache, return it, but also
-                        // update the entry in the cache in the background.
-                        event.waitUntil(cache.add(event.request));
-                        return cachedResponse;
-                    }}
-            
-                    // If we didn't find a match in the cache, use the network and cache it for later.
-                    const response = await fetch(event.request);
-                    cache.put(event.request, response.clone());
-                    return response;
-                }}());
-            }});
-"##,version_from_date).as_bytes()
-    ));
+    zip.add_text_file(OFFLINE_URL, &offline_html)
+}
+
+/// add service_worker.js to zip, it must be the last file, because it precaches all the other files
+pub fn add_service_worker_js_to_zip(
+    zip: &mut PwaZip,
+    caching_rules: &CachingRules,
+    precache_extra: &[PrecacheEntry],
+    offline_url: Option<&str>,
+    pwa_cache_version: &str,
+    skip_waiting_on_install: bool,
+) -> Result<(), HelperError> {
+    let mut precache = zip.files.clone();
+    precache.extend_from_slice(precache_extra);
+    let cache_name = match pwa_cache_version {
+        // the same files give the same CACHE_NAME, the users keep their cache after a rebuild
+        "content-hash" => version_from_content(&precache),
+        _ => {
+            let now = zip.now;
+            format!("{}.{}{:02}.{}{:02}", now.year(), now.month(), now.day(), now.hour(), now.minute())
+        }
+    };
+    let service_worker = service_worker_js(&cache_name, caching_rules, &precache, offline_url, skip_waiting_on_install);
+    zip.add_text_file("service_worker.js", &service_worker)
 }
 
 /// add start_service_worker.js to zip
-pub fn add_start_service_worker_js_to_zip(
-    zip: &mut zip::ZipWriter<std::io::Cursor<&mut [u8]>>,
-    now: &zip::DateTime,
-    pwa_folder: &str,
-) {
-    debug_write(&format!("add_start_service_worker_js_to_zip"));
-    let options = zip::write::FileOptions::default()
-        .compression_method(zip::CompressionMethod::Stored)
-        .last_modified_time(*now);
-    unwrap!(zip.start_file(&format!("{}/start_service_worker.js", pwa_folder), options));
-    use std::io::Write;
-    unwrap!(zip.write(
-        br##"
-            if ('serviceWorker' in navigator) {
-                navigator.serviceWorker.register('service_worker.js').then(function (registration) {
-                    console.log('Registration succeeded.');
-                }).catch(function (error) {
-                    console.log('Registration failed with ' + error);
-                });
-            };
-            //Listen for claiming of our ServiceWorker
-            navigator.serviceWorker.addEventListener('controllerchange', function () {
-                console.log('Service worker status changed: ', this.controller.state);
-                // Listen for changes in the state of our ServiceWorker
-                navigator.serviceWorker.controller.addEventListener('statechange', function () {
-                    // If the ServiceWorker becomes "activated", let the user know they can go offline!
-                    if (this.state === 'activated') {
-                        window.location.reload();
-                    }
-                });
-            });
-"##
-    ));
+pub fn add_start_service_worker_js_to_zip(zip: &mut PwaZip, update_banner_text: Option<&str>) -> Result<(), HelperError> {
+    zip.add_text_file("start_service_worker.js", &start_service_worker_js(update_banner_text))
 }
 
-/// finish zip
-pub fn finish_zip(zip: &mut zip::ZipWriter<std::io::Cursor<&mut [u8]>>) -> String {
-    debug_write(&format!("finish_zip"));
-    let zip_result: std::io::Cursor<&mut [u8]> = unwrap!(zip.finish());
-    let pos = zip_result.position();
-    let mut vec_u8 = zip_result.into_inner().to_vec();
-    vec_u8.truncate(pos as usize);
-    let url = create_download_url(vec_u8);
-    // return
-    url
+/// the source is a svg or a raster image
+pub fn decode_source_image(image_bytes: &[u8]) -> Result<SourceImage, HelperError> {
+    if is_svg(image_bytes) {
+        SourceImage::from_svg(image_bytes)
+    } else {
+        Ok(SourceImage::Raster(decode_png(image_bytes)?))
+    }
+}
+
+/// svg is xml text, the raster formats are binary with a signature.
+/// The xml prolog, the doctype and the comments of the editors can be long, so the whole file is searched for `<svg`.
+pub fn is_svg(image_bytes: &[u8]) -> bool {
+    let start = &image_bytes[..image_bytes.len().min(16)];
+    let start = String::from_utf8_lossy(start);
+    start.trim_start_matches('\u{feff}').trim_start().starts_with('<') && image_bytes.windows(4).any(|window| window == b"<svg")
 }
 
 /// decode png
-pub fn decode_png(vec: Vec<u8>) -> image::DynamicImage {
-    let img = image::io::Reader::new(std::io::Cursor::new(vec));
-    let img = unwrap!(img.with_guessed_format());
-    let img = unwrap!(img.decode());
+pub fn decode_png(image_bytes: &[u8]) -> Result<image::DynamicImage, HelperError> {
+    let img = image::io::Reader::new(std::io::Cursor::new(image_bytes));
+    let img = img.with_guessed_format().map_err(|err| HelperError::ImageDecode(err.to_string()))?;
+    let img = img.decode().map_err(|err| HelperError::ImageDecode(err.to_string()))?;
     // return
-    img
+    Ok(img)
 }
 
 /// encode to png
-pub fn encode_to_png(new_img: image::DynamicImage) -> Vec<u8> {
-    debug_write(&format!("encode new_img"));
+pub fn encode_to_png(new_img: &image::DynamicImage) -> Result<Vec<u8>, HelperError> {
     let mut vec_u8: Vec<u8> = Vec::new();
-    let _x = unwrap!(new_img.write_to(&mut vec_u8, image::ImageOutputFormat::Png));
+    new_img
+        .write_to(&mut vec_u8, image::ImageOutputFormat::Png)
+        .map_err(|err| HelperError::ImageEncode(err.to_string()))?;
     // return
-    vec_u8
+    Ok(vec_u8)
 }
 
-// favicon.ico with 16 and 32 icons
+// favicon.ico with 16, 32 and 48 icons
 pub fn encode_to_favicon_ico_and_add_to_zip(
-    zip: &mut zip::ZipWriter<std::io::Cursor<&mut [u8]>>,
-    img: &image::DynamicImage,
-    now: &zip::DateTime,
-    pwa_folder: &str,
-) {
+    zip: &mut PwaZip,
+    sources: &SourceSet,
+    resize_options: ResizeOptions,
+    image_compression: zip::CompressionMethod,
+) -> Result<(), HelperError> {
     // Create a new, empty icon collection:
     let mut icon_dir = ico::IconDir::new(ico::ResourceType::Icon);
-    favicon_add_entry(img, 16, &mut icon_dir);
-    favicon_add_entry(img, 32, &mut icon_dir);
-    favicon_add_entry(img, 48, &mut icon_dir);
+    favicon_add_entry(sources.for_size(16), 16, resize_options, &mut icon_dir)?;
+    favicon_add_entry(sources.for_size(32), 32, resize_options, &mut icon_dir)?;
+    favicon_add_entry(sources.for_size(48), 48, resize_options, &mut icon_dir)?;
 
     // Finally, add the ICO file to zip:
-    let options = zip::write::FileOptions::default()
-        .compression_method(zip::CompressionMethod::Stored)
-        .last_modified_time(*now);
-    unwrap!(zip.start_file(&format!("{}/favicon.ico", pwa_folder,), options));
-    unwrap!(icon_dir.write(zip));
+    let mut vec_u8: Vec<u8> = Vec::new();
+    icon_dir.write(&mut vec_u8)?;
+    zip.add_file("favicon.ico", &vec_u8, image_compression)
 }
 
-pub fn favicon_add_entry(img: &image::DynamicImage, size: u32, icon_dir: &mut ico::IconDir) {
+pub fn favicon_add_entry(
+    source: &SourceImage,
+    size: u32,
+    resize_options: ResizeOptions,
+    icon_dir: &mut ico::IconDir,
+) -> Result<(), HelperError> {
     // icons need smaller images 48, 32 and 16
-    let img_rgba_vec = img
-        .resize(size, size, image::imageops::FilterType::Lanczos3)
-        .into_rgba8()
-        .into_raw();
+    let img = source.resize(size, resize_options).into_rgba8();
     // create an IconImage from raw RGBA pixel data from another image library
-    let icon_image = ico::IconImage::from_rgba_data(size, size, img_rgba_vec);
-    icon_dir.add_entry(ico::IconDirEntry::encode(&icon_image).unwrap());
+    let icon_image = ico::IconImage::from_rgba_data(img.width(), img.height(), img.into_raw());
+    icon_dir.add_entry(ico::IconDirEntry::encode(&icon_image)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the file names in the zip with the pwa folder
+    fn zip_file_names(zip: &[u8]) -> Vec<String> {
+        let archive = zip::ZipArchive::new(std::io::Cursor::new(zip)).unwrap();
+        archive.file_names().map(|name| name.to_string()).collect()
+    }
+
+    /// the content of a text file in the zip
+    fn zip_file_text(zip: &[u8], name: &str) -> String {
+        use std::io::Read;
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(zip)).unwrap();
+        let mut text = String::new();
+        archive.by_name(name).unwrap().read_to_string(&mut text).unwrap();
+        text
+    }
+
+    #[test]
+    fn generate_pwa_zip_from_a_tiny_png() {
+        let png = encode_to_png(&image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            64,
+            64,
+            image::Rgba([255, 0, 0, 255]),
+        )))
+        .unwrap();
+        let pwa_data = PwaData {
+            pwa_short_name: "Ex".to_string(),
+            pwa_name: r#"The "Example" app"#.to_string(),
+            pwa_folder: "example".to_string(),
+            pwa_png_optimization: false,
+            ..Default::default()
+        };
+        let generated = generate_pwa_zip(&pwa_data, &png, &[], zip::DateTime::default()).unwrap();
+        // the tiny source is upscaled and it has no transparent border
+        assert_eq!(
+            generated.warnings,
+            vec!["The source image 64x64 is smaller than 512x512, the bigger icons are upscaled and blurry."]
+        );
+        assert_eq!(generated.icons.len(), 17);
+
+        let names = zip_file_names(&generated.zip);
+        for name in [
+            "example/favicon.ico",
+            "example/icons/icon-032.png",
+            "example/icons/icon-512.png",
+            "example/icons/icon-maskable-192.png",
+            "example/icons/mstile-310x150.png",
+            "example/browserconfig.xml",
+            "example/manifest.json",
+            "example/index.html",
+            "example/start_service_worker.js",
+            "example/service_worker.js",
+        ]
+        .iter()
+        {
+            assert!(names.iter().any(|file_name| file_name == name), "{} not in {:?}", name, names);
+        }
+        // the options are off by default
+        assert!(!names
+            .iter()
+            .any(|name| name.contains("splash") || name.ends_with("offline.html") || name.ends_with("install_prompt.js")));
+
+        let manifest: serde_json::Value = serde_json::from_str(&zip_file_text(&generated.zip, "example/manifest.json")).unwrap();
+        assert_eq!(manifest["short_name"], "Ex");
+        assert_eq!(manifest["name"], r#"The "Example" app"#);
+        assert_eq!(manifest["start_url"], "/example/index.html");
+        let icons = manifest["icons"].as_array().unwrap();
+        assert_eq!(icons.len(), 9);
+        assert!(icons.iter().all(|icon| icon["type"] == "image/png"));
+        assert!(icons
+            .iter()
+            .any(|icon| icon["src"] == "icons/icon-maskable-512.png" && icon["purpose"] == "maskable"));
+    }
+
+    #[test]
+    fn svg_with_a_long_prolog_is_svg() {
+        let mut svg = String::from("\u{feff}<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        svg.push_str("<!DOCTYPE svg PUBLIC \"-//W3C//DTD SVG 1.1//EN\" \"http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd\">\n");
+        svg.push_str(&format!("<!-- {} -->\n", "Generator: Adobe Illustrator ".repeat(100)));
+        svg.push_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10"><rect width="10" height="10"/></svg>"#);
+        assert!(svg.len() > 2048);
+        assert!(is_svg(svg.as_bytes()));
+        assert!(decode_source_image(svg.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn png_and_html_are_not_svg() {
+        let png = encode_to_png(&image::DynamicImage::new_rgba8(4, 4)).unwrap();
+        assert!(!is_svg(&png));
+        assert!(!is_svg(b"<html><body>no image</body></html>"));
+        assert!(!is_svg(b""));
+    }
 }
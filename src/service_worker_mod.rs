@@ -0,0 +1,579 @@
+//! service_worker_mod.rs
+//! The generated service_worker.js and start_service_worker.js.
+//! The caching strategy is selectable per URL pattern. The rules are declared as data,
+//! one rule per line, for easy editing in a textarea and storing in local_storage:
+//! `pattern strategy`
+//! The first rule that matches the request is used. Requests without a matching rule go to the network.
+//! All the files in the zip are precached on install, with a content hash for cache busting.
+//! The optional offline.html is the fallback for the navigations when offline.
+//! The new version is activated immediately with a reload, or it waits until the user clicks the update banner.
+//! Pure Rust, no javascript objects here.
+
+use crate::error_mod::HelperError;
+use crate::utils_mod::content_hash;
+
+/// the default caching rules
+pub const DEFAULT_CACHING_RULES: &str = r#"navigation network-first
+*.html network-first
+pkg/*.wasm cache-first
+pkg/*.js cache-first
+** stale-while-revalidate"#;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CachingStrategy {
+    /// the cache, if missing then the network and store it in the cache
+    CacheFirst,
+    /// the network and store it in the cache, if offline then the cache
+    NetworkFirst,
+    /// the cache and update the cache in the background, if missing then the network
+    StaleWhileRevalidate,
+    /// always the network, never cached
+    NetworkOnly,
+    /// only the cache, for files that are precached
+    CacheOnly,
+}
+
+impl CachingStrategy {
+    /// the value used in the text format
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CachingStrategy::CacheFirst => "cache-first",
+            CachingStrategy::NetworkFirst => "network-first",
+            CachingStrategy::StaleWhileRevalidate => "stale-while-revalidate",
+            CachingStrategy::NetworkOnly => "network-only",
+            CachingStrategy::CacheOnly => "cache-only",
+        }
+    }
+    /// the name of the javascript function in service_worker.js
+    pub fn js_function(&self) -> &'static str {
+        match self {
+            CachingStrategy::CacheFirst => "cacheFirst",
+            CachingStrategy::NetworkFirst => "networkFirst",
+            CachingStrategy::StaleWhileRevalidate => "staleWhileRevalidate",
+            CachingStrategy::NetworkOnly => "networkOnly",
+            CachingStrategy::CacheOnly => "cacheOnly",
+        }
+    }
+    fn parse(text: &str) -> Option<CachingStrategy> {
+        [
+            CachingStrategy::CacheFirst,
+            CachingStrategy::NetworkFirst,
+            CachingStrategy::StaleWhileRevalidate,
+            CachingStrategy::NetworkOnly,
+            CachingStrategy::CacheOnly,
+        ]
+        .iter()
+        .find(|strategy| strategy.as_str() == text)
+        .copied()
+    }
+}
+
+/// The pattern is a glob relative to the pwa folder: `*` inside one folder, `**` across folders.
+/// A pattern starting with / is an absolute path on the server, it is matched against the whole url pathname.
+/// The special pattern `navigation` matches the page navigations.
+#[derive(Clone, Debug)]
+pub struct CachingRule {
+    pub pattern: String,
+    pub strategy: CachingStrategy,
+}
+
+#[derive(Clone, Debug)]
+pub struct CachingRules {
+    pub rules: Vec<CachingRule>,
+}
+
+impl CachingRules {
+    /// parse the text format, one rule per line. Empty lines and lines starting with # are ignored.
+    pub fn parse(text: &str) -> Result<CachingRules, HelperError> {
+        let mut rules = vec![];
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let rule = parse_line(line).map_err(|err| HelperError::Input(format!("Caching rules line {}: {}", line_number + 1, err)))?;
+            rules.push(rule);
+        }
+        // return
+        Ok(CachingRules { rules })
+    }
+
+    /// the rules as a javascript array, the globs are converted to regular expressions
+    pub fn to_js_routes(&self) -> String {
+        let mut js = String::new();
+        for rule in self.rules.iter() {
+            let (navigation, regex) = if rule.pattern == "navigation" {
+                (true, String::new())
+            } else {
+                (false, glob_to_regex(&rule.pattern))
+            };
+            // absolute patterns are tested against the url pathname, the others against the path relative to the scope
+            let absolute = rule.pattern.starts_with('/');
+            js.push_str(&format!(
+                "    {{ navigation: {}, absolute: {}, regex: new RegExp({}), strategy: {} }}, // {} {}\n",
+                navigation,
+                absolute,
+                serde_json::to_string(&regex).unwrap_or_default(),
+                rule.strategy.js_function(),
+                rule.pattern,
+                rule.strategy.as_str()
+            ));
+        }
+        // return
+        js
+    }
+}
+
+/// one line: pattern strategy
+fn parse_line(line: &str) -> Result<CachingRule, String> {
+    let columns: Vec<&str> = line.split_whitespace().collect();
+    if columns.len() != 2 {
+        return Err(format!("expected 2 columns `pattern strategy`, found {}", columns.len()));
+    }
+    let strategy = CachingStrategy::parse(columns[1]).ok_or_else(|| {
+        format!(
+            "unknown strategy `{}`, use cache-first, network-first, stale-while-revalidate, network-only or cache-only",
+            columns[1]
+        )
+    })?;
+    // return
+    Ok(CachingRule {
+        pattern: columns[0].to_string(),
+        strategy,
+    })
+}
+
+/// a file to precache on install, the url is relative to the pwa folder
+#[derive(Clone, Debug)]
+pub struct PrecacheEntry {
+    pub url: String,
+    /// the content hash, None for the extra files that are not in the zip
+    pub revision: Option<String>,
+}
+
+/// parse the extra paths to precache, one path per line. Empty lines and lines starting with # are ignored.
+/// The service worker cannot list the files on the server, so wildcards are not possible.
+pub fn parse_precache_extra(text: &str) -> Result<Vec<PrecacheEntry>, HelperError> {
+    let mut entries = vec![];
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.contains('*') || line.contains('?') {
+            return Err(HelperError::Input(format!(
+                "Precache line {}: `{}` has a wildcard, write the exact path like pkg/app_bg.wasm",
+                line_number + 1,
+                line
+            )));
+        }
+        entries.push(PrecacheEntry {
+            url: line.to_string(),
+            revision: None,
+        });
+    }
+    // return
+    Ok(entries)
+}
+
+/// the cache version from the urls and the content hashes of all precached files.
+/// The extra files are not in the zip, so only their path is part of the version.
+pub fn version_from_content(precache: &[PrecacheEntry]) -> String {
+    let mut text = String::new();
+    for entry in precache.iter() {
+        text.push_str(&format!("{} {}\n", entry.url, entry.revision.as_deref().unwrap_or("")));
+    }
+    // return
+    content_hash(text.as_bytes())
+}
+
+/// the precache list as a javascript array
+fn precache_js(precache: &[PrecacheEntry]) -> String {
+    let mut js = String::new();
+    for entry in precache.iter() {
+        js.push_str(&format!(
+            "    {{ url: {}, revision: {} }},\n",
+            serde_json::to_string(&entry.url).unwrap_or_default(),
+            match &entry.revision {
+                Some(revision) => format!("'{}'", revision),
+                None => "null".to_string(),
+            }
+        ));
+    }
+    // return
+    js
+}
+
+/// glob to an anchored regular expression: `**` is any path, `*` and `?` stay inside one folder
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex.push_str(".*");
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '.' | '+' | '(' | ')' | '|' | '^' | '$' | '[' | ']' | '{' | '}' | '\\' => {
+                regex.push('\\');
+                regex.push(c);
+            }
+            _ => regex.push(c),
+        }
+    }
+    regex.push('$');
+    // return
+    regex
+}
+
+/// the content of service_worker.js
+pub fn service_worker_js(
+    cache_name: &str,
+    caching_rules: &CachingRules,
+    precache: &[PrecacheEntry],
+    offline_url: Option<&str>,
+    skip_waiting_on_install: bool,
+) -> String {
+    let offline_url = match offline_url {
+        Some(url) => serde_json::to_string(url).unwrap_or_default(),
+        None => "null".to_string(),
+    };
+    SERVICE_WORKER_JS
+        .replace("__CACHE_NAME__", cache_name)
+        .replace("__OFFLINE_URL__", &offline_url)
+        .replace("__SKIP_WAITING_ON_INSTALL__", &skip_waiting_on_install.to_string())
+        .replace("__ROUTES__", &caching_rules.to_js_routes())
+        .replace("__PRECACHE__", &precache_js(precache))
+}
+
+/// the content of start_service_worker.js
+/// Without the banner text, the new service worker is activated immediately and the page reloads.
+/// With the banner text, the new service worker waits and the page shows the banner.
+/// Only the click on the banner posts SKIP_WAITING to the waiting service worker.
+pub fn start_service_worker_js(update_banner_text: Option<&str>) -> String {
+    match update_banner_text {
+        None => START_SERVICE_WORKER_JS.to_string(),
+        Some(text) => {
+            START_SERVICE_WORKER_WITH_BANNER_JS.replace("__UPDATE_BANNER_TEXT__", &serde_json::to_string(text).unwrap_or_default())
+        }
+    }
+}
+
+/// The template has placeholders like __CACHE_NAME__, because javascript has a lot of braces for format!.
+const SERVICE_WORKER_JS: &str = r##"'use strict';
+
+// Incrementing VERSION in CACHE_NAME will kick off the
+// install event and force previously cached
+// resources to be cached again.
+// but the new service worker will not be activated until all
+// tabs with this webapp are closed.
+
+const CACHE_NAME = '__CACHE_NAME__';
+
+// the caching strategy for the requests, the first matching route is used.
+// The regex is tested on the path relative to the service worker scope, the absolute ones on the url pathname.
+const ROUTES = [
+__ROUTES__];
+
+// all the files are cached on install, so they are available offline before they are visited.
+// The revision is the content hash, it busts the http cache of the browser and of proxies.
+const PRECACHE = [
+__PRECACHE__];
+
+// true: the new version is activated immediately,
+// false: it waits until the user clicks the update banner
+const SKIP_WAITING_ON_INSTALL = __SKIP_WAITING_ON_INSTALL__;
+
+// the fallback page for navigations when offline, null if not generated
+const OFFLINE_URL = __OFFLINE_URL__;
+
+self.addEventListener('install', event => {
+    console.log('event install ', CACHE_NAME);
+    if (SKIP_WAITING_ON_INSTALL) {
+        // the ugly trick of avoiding the waiting phase
+        self.skipWaiting();
+    }
+
+    event.waitUntil(
+        caches.open(CACHE_NAME).then(function (cache) {
+            return Promise.all(PRECACHE.map(entry => {
+                const url = entry.revision ? entry.url + '?__revision=' + entry.revision : entry.url;
+                return fetch(new Request(url, { cache: 'reload' })).then(response => {
+                    if (!response.ok) throw new Error('Precache failed for ' + entry.url);
+                    // stored without the revision, so the normal requests find it
+                    return cache.put(entry.url, response);
+                });
+            }));
+        })
+    );
+});
+
+// the page posts SKIP_WAITING when the user clicks the update banner
+self.addEventListener('message', event => {
+    if (event.data && event.data.type === 'SKIP_WAITING') {
+        self.skipWaiting();
+    }
+});
+
+self.addEventListener('activate', event => {
+    console.log('event activate');
+    // Delete all caches that aren't CACHE_NAME.
+    event.waitUntil(
+        caches.keys().then(cacheNames => {
+            return Promise.all(
+                cacheNames.map(cacheName => {
+                    if (CACHE_NAME.indexOf(cacheName) === -1) {
+                        // If this cache name isn't right, then delete it.
+                        console.log('Deleting out of date cache:', cacheName);
+                        return caches.delete(cacheName);
+                    }
+                })
+            );
+        })
+    );
+});
+
+self.addEventListener('fetch', event => {
+    // Let the browser do its default thing
+    // for non-GET requests.
+    if (event.request.method != 'GET') return;
+
+    const route = findRoute(event.request);
+    const isNavigation = event.request.mode === 'navigate';
+    // without a matching route the browser does its default thing
+    if (!route && !(isNavigation && OFFLINE_URL)) return;
+    const strategy = route ? route.strategy : networkOnly;
+    event.respondWith(strategy(event.request, event).catch(error => {
+        // offline navigations get the offline page instead of the browser error page
+        if (isNavigation && OFFLINE_URL) {
+            return caches.match(OFFLINE_URL).then(response => response || Promise.reject(error));
+        }
+        throw error;
+    }));
+});
+
+// the path relative to the scope, or null outside of the scope
+function relativePath(url) {
+    const scopePath = new URL(self.registration.scope).pathname;
+    if (url.pathname.startsWith(scopePath)) {
+        return url.pathname.slice(scopePath.length);
+    }
+    return null;
+}
+
+function findRoute(request) {
+    const url = new URL(request.url);
+    if (url.origin !== self.location.origin) return null;
+    const path = relativePath(url);
+    const isNavigation = request.mode === 'navigate';
+    return ROUTES.find(route => {
+        if (route.navigation) return isNavigation;
+        // the absolute patterns start with / and are tested against the whole pathname
+        if (route.absolute) return route.regex.test(url.pathname);
+        return path !== null && route.regex.test(path);
+    });
+}
+
+async function cacheFirst(request) {
+    const cache = await caches.open(CACHE_NAME);
+    const cachedResponse = await cache.match(request);
+    if (cachedResponse) return cachedResponse;
+    const response = await fetch(request);
+    if (response.ok) cache.put(request, response.clone());
+    return response;
+}
+
+async function networkFirst(request) {
+    const cache = await caches.open(CACHE_NAME);
+    try {
+        const response = await fetch(request);
+        if (response.ok) cache.put(request, response.clone());
+        return response;
+    } catch (error) {
+        const cachedResponse = await cache.match(request);
+        if (cachedResponse) return cachedResponse;
+        throw error;
+    }
+}
+
+async function staleWhileRevalidate(request, event) {
+    const cache = await caches.open(CACHE_NAME);
+    const cachedResponse = await cache.match(request);
+    const networkResponse = fetch(request).then(response => {
+        if (response.ok) cache.put(request, response.clone());
+        return response;
+    });
+    if (cachedResponse) {
+        // update the entry in the cache in the background
+        event.waitUntil(networkResponse.catch(() => { }));
+        return cachedResponse;
+    }
+    return networkResponse;
+}
+
+function networkOnly(request) {
+    return fetch(request);
+}
+
+async function cacheOnly(request) {
+    const cache = await caches.open(CACHE_NAME);
+    const cachedResponse = await cache.match(request);
+    // a rejection and not Response.error(), so an offline navigation gets the offline page
+    if (!cachedResponse) throw new Error('not in cache: ' + request.url);
+    return cachedResponse;
+}
+"##;
+
+const START_SERVICE_WORKER_JS: &str = r##"if ('serviceWorker' in navigator) {
+    navigator.serviceWorker.register('service_worker.js').then(function (registration) {
+        console.log('Registration succeeded.');
+    }).catch(function (error) {
+        console.log('Registration failed with ' + error);
+    });
+};
+//Listen for claiming of our ServiceWorker
+navigator.serviceWorker.addEventListener('controllerchange', function () {
+    console.log('Service worker status changed: ', this.controller.state);
+    // Listen for changes in the state of our ServiceWorker
+    navigator.serviceWorker.controller.addEventListener('statechange', function () {
+        // If the ServiceWorker becomes "activated", let the user know they can go offline!
+        if (this.state === 'activated') {
+            window.location.reload();
+        }
+    });
+});
+"##;
+
+const START_SERVICE_WORKER_WITH_BANNER_JS: &str = r##"const UPDATE_BANNER_TEXT = __UPDATE_BANNER_TEXT__;
+
+if ('serviceWorker' in navigator) {
+    // the page reloads only after the user clicked the banner
+    let refreshing = false;
+    navigator.serviceWorker.addEventListener('controllerchange', function () {
+        if (refreshing) return;
+        refreshing = true;
+        window.location.reload();
+    });
+    navigator.serviceWorker.register('service_worker.js').then(function (registration) {
+        console.log('Registration succeeded.');
+        // the new version was already waiting from a previous visit
+        if (registration.waiting && navigator.serviceWorker.controller) {
+            showUpdateBanner(registration.waiting);
+        }
+        registration.addEventListener('updatefound', function () {
+            const newWorker = registration.installing;
+            newWorker.addEventListener('statechange', function () {
+                // installed with an existing controller is an update, not the first install
+                if (newWorker.state === 'installed' && navigator.serviceWorker.controller) {
+                    showUpdateBanner(newWorker);
+                }
+            });
+        });
+    }).catch(function (error) {
+        console.log('Registration failed with ' + error);
+    });
+};
+
+// the form in the page is not lost, the user decides when to reload
+function showUpdateBanner(worker) {
+    if (document.getElementById('update_banner')) return;
+    const banner = document.createElement('div');
+    banner.id = 'update_banner';
+    banner.textContent = UPDATE_BANNER_TEXT;
+    banner.style.cssText = 'position:fixed; left:0; right:0; bottom:0; padding:16px; text-align:center; cursor:pointer; background-color:#333333; color:#ffffff; z-index:1000;';
+    banner.addEventListener('click', function () {
+        worker.postMessage({ type: 'SKIP_WAITING' });
+    });
+    document.body.appendChild(banner);
+}
+"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_to_regex_is_anchored() {
+        assert_eq!(glob_to_regex("index.html"), r"^index\.html$");
+        assert_eq!(glob_to_regex(""), "^$");
+    }
+
+    #[test]
+    fn glob_to_regex_wildcards() {
+        assert_eq!(glob_to_regex("icons/*.png"), r"^icons/[^/]*\.png$");
+        assert_eq!(glob_to_regex("/api/**"), "^/api/.*$");
+        assert_eq!(glob_to_regex("icon-?.png"), r"^icon-[^/]\.png$");
+    }
+
+    #[test]
+    fn glob_to_regex_escapes_regex_characters() {
+        assert_eq!(glob_to_regex("a+b(c)|d^e$[f]{g}\\h"), r"^a\+b\(c\)\|d\^e\$\[f\]\{g\}\\h$");
+        // the characters without a meaning in the regex stay as they are
+        assert_eq!(glob_to_regex("pkg/my-app_bg.wasm"), r"^pkg/my-app_bg\.wasm$");
+    }
+
+    #[test]
+    fn to_js_routes_marks_the_absolute_patterns() {
+        let js = CachingRules::parse("/api/** network-only\nicons/*.png cache-first")
+            .unwrap()
+            .to_js_routes();
+        let lines: Vec<&str> = js.lines().collect();
+        assert!(lines[0].contains("absolute: true"), "{}", lines[0]);
+        assert!(lines[1].contains("absolute: false"), "{}", lines[1]);
+    }
+
+    fn precache(files: &[(&str, &[u8])]) -> Vec<PrecacheEntry> {
+        files
+            .iter()
+            .map(|(url, content)| PrecacheEntry {
+                url: url.to_string(),
+                revision: Some(content_hash(content)),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn version_from_content_is_deterministic() {
+        let files: &[(&str, &[u8])] = &[("index.html", b"<html></html>"), ("icons/icon-192.png", b"png bytes")];
+        assert_eq!(version_from_content(&precache(files)), version_from_content(&precache(files)));
+        assert_eq!(version_from_content(&precache(files)).len(), 16);
+    }
+
+    #[test]
+    fn version_from_content_changes_with_one_byte() {
+        let before = version_from_content(&precache(&[("index.html", b"<html></html>"), ("icons/icon-192.png", b"png bytes")]));
+        let after = version_from_content(&precache(&[("index.html", b"<html></html>"), ("icons/icon-192.png", b"png bytez")]));
+        assert_ne!(before, after);
+        // also the url is part of the version
+        let renamed = version_from_content(&precache(&[("index.html", b"<html></html>"), ("icons/icon-193.png", b"png bytes")]));
+        assert_ne!(before, renamed);
+    }
+
+    #[test]
+    fn caching_rules_parse_skips_comments_and_empty_lines() {
+        let rules = CachingRules::parse("# comment\n\n  icons/*.png cache-first  \nnavigation network-first\n").unwrap();
+        assert_eq!(rules.rules.len(), 2);
+        assert_eq!(rules.rules[0].pattern, "icons/*.png");
+        assert_eq!(rules.rules[0].strategy, CachingStrategy::CacheFirst);
+        assert_eq!(rules.rules[1].pattern, "navigation");
+        assert_eq!(rules.rules[1].strategy, CachingStrategy::NetworkFirst);
+    }
+
+    #[test]
+    fn caching_rules_parse_reports_the_bad_line() {
+        let err = CachingRules::parse("index.html cache-first\n\nicons/*.png cache-forever").unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("line 3"), "{}", message);
+        assert!(message.contains("unknown strategy `cache-forever`"), "{}", message);
+    }
+
+    #[test]
+    fn caching_rules_parse_needs_two_columns() {
+        let message = CachingRules::parse("icons/*.png").unwrap_err().to_string();
+        assert!(message.contains("line 1") && message.contains("found 1"), "{}", message);
+        let message = CachingRules::parse("icons/*.png cache-first extra").unwrap_err().to_string();
+        assert!(message.contains("found 3"), "{}", message);
+    }
+}
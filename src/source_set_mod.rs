@@ -0,0 +1,104 @@
+//! source_set_mod.rs
+//! Detailed logos turn into mush when 16px and 32px icons are downscaled from a 512px source.
+//! The designer can add hand-tuned small sources (e.g. 16, 32, 48) beside the main source.
+//! For every target size the generator picks the nearest larger source.
+//! Pure Rust, no javascript objects here.
+
+use crate::error_mod::HelperError;
+use crate::image_processing_mod::SourceImage;
+
+/// the main source and the optional small sources, sorted by size from the smallest
+pub struct SourceSet {
+    /// the main source is validated, cropped and used for the big images like splash and tiles
+    pub main: SourceImage,
+    small_sources: Vec<(u32, SourceImage)>,
+}
+
+impl SourceSet {
+    /// the small sources must be square, they are not padded or cropped
+    pub fn new(main: SourceImage, small_sources: Vec<SourceImage>) -> Result<SourceSet, HelperError> {
+        let mut sized_sources = vec![];
+        for source in small_sources.into_iter() {
+            let (width, height) = source.dimensions();
+            if width != height {
+                return Err(HelperError::Input(format!(
+                    "The small source {}x{} must be square. It is used as it is, without pad or crop.",
+                    width, height
+                )));
+            }
+            sized_sources.push((width, source));
+        }
+        sized_sources.sort_by_key(|(size, _source)| *size);
+        // return
+        Ok(SourceSet {
+            main,
+            small_sources: sized_sources,
+        })
+    }
+
+    /// the smallest source that is not smaller than the target size, or else the main source
+    pub fn for_size(&self, size: u32) -> &SourceImage {
+        // a svg main source is scalable, so it is the biggest anyway
+        let main_size = match &self.main {
+            SourceImage::Svg(..) => u32::MAX,
+            SourceImage::Raster(_) => self.main.dimensions().0.min(self.main.dimensions().1),
+        };
+        self.small_sources
+            .iter()
+            .find(|(source_size, _source)| *source_size >= size && *source_size < main_size)
+            .map(|(_size, source)| source)
+            .unwrap_or(&self.main)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raster(width: u32, height: u32) -> SourceImage {
+        SourceImage::Raster(image::DynamicImage::new_rgba8(width, height))
+    }
+
+    fn size_for(sources: &SourceSet, size: u32) -> u32 {
+        sources.for_size(size).dimensions().0
+    }
+
+    #[test]
+    fn for_size_picks_the_smallest_source_not_smaller_than_the_size() {
+        // the small sources are sorted, the order of the files does not matter
+        let sources = SourceSet::new(raster(512, 512), vec![raster(48, 48), raster(16, 16), raster(32, 32)]).unwrap();
+        assert_eq!(size_for(&sources, 16), 16);
+        assert_eq!(size_for(&sources, 20), 32);
+        assert_eq!(size_for(&sources, 32), 32);
+        assert_eq!(size_for(&sources, 48), 48);
+        assert_eq!(size_for(&sources, 8), 16);
+    }
+
+    #[test]
+    fn for_size_falls_back_to_the_main_source() {
+        let sources = SourceSet::new(raster(512, 512), vec![raster(16, 16)]).unwrap();
+        assert_eq!(size_for(&sources, 64), 512);
+        assert_eq!(size_for(&sources, 1024), 512);
+        let sources = SourceSet::new(raster(512, 512), vec![]).unwrap();
+        assert_eq!(size_for(&sources, 16), 512);
+    }
+
+    #[test]
+    fn a_small_source_bigger_than_the_main_source_is_not_used() {
+        let sources = SourceSet::new(raster(256, 256), vec![raster(512, 512)]).unwrap();
+        assert_eq!(size_for(&sources, 300), 256);
+    }
+
+    #[test]
+    fn a_svg_main_source_is_the_biggest() {
+        let svg = br#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10"><rect width="10" height="10"/></svg>"#;
+        let sources = SourceSet::new(SourceImage::from_svg(svg).unwrap(), vec![raster(1024, 1024)]).unwrap();
+        assert_eq!(size_for(&sources, 512), 1024);
+        assert!(matches!(sources.for_size(2048), SourceImage::Svg(..)));
+    }
+
+    #[test]
+    fn small_sources_must_be_square() {
+        assert!(SourceSet::new(raster(512, 512), vec![raster(32, 16)]).is_err());
+    }
+}
@@ -0,0 +1,244 @@
+//! source_validation_mod.rs
+//! The source image is checked before generating. A 100x60 jpg silently produced blurry non-square icons.
+//! A non-square source is padded or cropped to square, or the generation is aborted.
+//! The other issues are reported as warnings, or they abort the generation too.
+//! Pure Rust, no javascript objects here.
+
+use crate::error_mod::HelperError;
+use crate::image_processing_mod::*;
+
+/// the biggest icon in the default icon set
+pub const MIN_SOURCE_SIZE: u32 = 512;
+
+/// the artwork should fill at least this part of the width or height
+const MIN_CONTENT_RATIO: f64 = 0.8;
+
+/// what to do with the issues of the source image
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SourceFix {
+    Pad,
+    Crop,
+    Abort,
+}
+
+impl SourceFix {
+    /// the values used in the select and in local_storage: pad, crop or abort
+    pub fn parse(text: &str) -> Result<SourceFix, HelperError> {
+        match text {
+            "pad" => Ok(SourceFix::Pad),
+            "crop" => Ok(SourceFix::Crop),
+            "abort" => Ok(SourceFix::Abort),
+            other => Err(HelperError::Input(format!(
+                "Source fix `{}` is unknown, expected pad, crop or abort",
+                other
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SourceIssue {
+    NotSquare {
+        width: u32,
+        height: u32,
+    },
+    TooSmall {
+        width: u32,
+        height: u32,
+    },
+    NoAlpha,
+    TransparentBorder {
+        content_percent: u32,
+    },
+    /// resvg is compiled without the text feature, because there are no fonts in wasm
+    SvgText,
+}
+
+impl std::fmt::Display for SourceIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SourceIssue::NotSquare { width, height } => write!(f, "The source image is not square: {}x{}.", width, height),
+            SourceIssue::TooSmall { width, height } => write!(
+                f,
+                "The source image {}x{} is smaller than {}x{}, the bigger icons are upscaled and blurry.",
+                width, height, MIN_SOURCE_SIZE, MIN_SOURCE_SIZE
+            ),
+            SourceIssue::NoAlpha => write!(f, "The source image has no alpha channel, the icons cannot be transparent."),
+            SourceIssue::TransparentBorder { content_percent } => write!(
+                f,
+                "The source image has a large transparent border, the artwork fills only {}%, the icons look small.",
+                content_percent
+            ),
+            SourceIssue::SvgText => write!(
+                f,
+                "The svg has text elements, they are not rendered and the icons have no lettering. Convert the text to paths."
+            ),
+        }
+    }
+}
+
+/// all the issues of the source image. The svg is scalable and can be transparent, only the shape and the text are checked.
+/// The image_bytes are the source file, the parsed svg has no text elements anymore.
+pub fn source_issues(source: &SourceImage, image_bytes: &[u8]) -> Vec<SourceIssue> {
+    let mut issues = vec![];
+    let (width, height) = source.dimensions();
+    if width != height {
+        issues.push(SourceIssue::NotSquare { width, height });
+    }
+    match source {
+        SourceImage::Raster(img) => {
+            if width.min(height) < MIN_SOURCE_SIZE {
+                issues.push(SourceIssue::TooSmall { width, height });
+            }
+            if !img.color().has_alpha() {
+                issues.push(SourceIssue::NoAlpha);
+            }
+        }
+        SourceImage::Svg(..) => {
+            if svg_has_text(image_bytes) {
+                issues.push(SourceIssue::SvgText);
+            }
+        }
+    }
+    // a small rendering is enough to find the transparent border
+    let content_percent = content_percent(&source.resize(256, ResizeOptions::default()).into_rgba8());
+    if content_percent < (MIN_CONTENT_RATIO * 100.0) as u32 {
+        issues.push(SourceIssue::TransparentBorder { content_percent });
+    }
+    // return
+    issues
+}
+
+/// true if the svg has a `<text>` element. A compressed svgz is not checked.
+fn svg_has_text(svg_bytes: &[u8]) -> bool {
+    let text = match std::str::from_utf8(svg_bytes) {
+        Ok(text) => text,
+        Err(_) => return false,
+    };
+    let options = resvg::usvg::roxmltree::ParsingOptions {
+        allow_dtd: true,
+        ..Default::default()
+    };
+    match resvg::usvg::roxmltree::Document::parse_with_options(text.trim_start_matches('\u{feff}'), options) {
+        Ok(document) => document.descendants().any(|node| node.tag_name().name() == "text"),
+        Err(_) => false,
+    }
+}
+
+/// the biggest part of the width or height that is not transparent, in percent
+fn content_percent(img: &image::RgbaImage) -> u32 {
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (u32::MAX, u32::MAX, 0, 0);
+    for (x, y, pixel) in img.enumerate_pixels() {
+        if pixel[3] > 0 {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+    }
+    if min_x > max_x {
+        // completely transparent
+        return 0;
+    }
+    let width_ratio = (max_x - min_x + 1) as f64 / img.width() as f64;
+    let height_ratio = (max_y - min_y + 1) as f64 / img.height() as f64;
+    // return
+    (width_ratio.max(height_ratio) * 100.0).round() as u32
+}
+
+/// check the source and make it square. Returns the warnings for the user.
+pub fn validate_source(source: SourceImage, image_bytes: &[u8], source_fix: SourceFix) -> Result<(SourceImage, Vec<String>), HelperError> {
+    let issues = source_issues(&source, image_bytes);
+    let mut warnings: Vec<String> = issues.iter().map(|issue| issue.to_string()).collect();
+    let source = match source_fix {
+        SourceFix::Abort if !issues.is_empty() => {
+            return Err(HelperError::Input(format!("Generation aborted. {}", warnings.join(" "))));
+        }
+        SourceFix::Abort => source,
+        SourceFix::Pad if !source.is_square() => {
+            warnings.push("It is padded to square with transparent.".to_string());
+            source.to_square(SquareFix::Pad)
+        }
+        SourceFix::Crop if !source.is_square() => {
+            warnings.push("It is cropped to the centered square.".to_string());
+            source.to_square(SquareFix::Crop)
+        }
+        _ => source,
+    };
+    // return
+    Ok((source, warnings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SVG_WITH_TEXT: &str =
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10"><rect width="10" height="10"/><text x="1" y="8">A</text></svg>"#;
+
+    /// an opaque square in the middle of a transparent image, the artwork fills 90%
+    fn raster(width: u32, height: u32) -> SourceImage {
+        SourceImage::Raster(image::DynamicImage::ImageRgba8(image::RgbaImage::from_fn(width, height, |x, y| {
+            let inside = x >= width / 20 && x < width - width / 20 && y >= height / 20 && y < height - height / 20;
+            image::Rgba([255, 0, 0, if inside { 255 } else { 0 }])
+        })))
+    }
+
+    #[test]
+    fn a_good_source_has_no_issues() {
+        let (source, warnings) = validate_source(raster(512, 512), b"", SourceFix::Abort).unwrap();
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(source.dimensions(), (512, 512));
+    }
+
+    #[test]
+    fn pad_makes_a_square_from_the_longer_side() {
+        let (source, warnings) = validate_source(raster(600, 512), b"", SourceFix::Pad).unwrap();
+        assert_eq!(source.dimensions(), (600, 600));
+        assert!(warnings.contains(&"The source image is not square: 600x512.".to_string()));
+        assert!(warnings.contains(&"It is padded to square with transparent.".to_string()));
+    }
+
+    #[test]
+    fn crop_makes_a_square_from_the_shorter_side() {
+        let (source, warnings) = validate_source(raster(600, 512), b"", SourceFix::Crop).unwrap();
+        assert_eq!(source.dimensions(), (512, 512));
+        assert!(warnings.contains(&"It is cropped to the centered square.".to_string()));
+    }
+
+    #[test]
+    fn abort_returns_the_issues_as_error() {
+        let message = validate_source(raster(600, 512), b"", SourceFix::Abort).err().unwrap().to_string();
+        assert!(
+            message.contains("Generation aborted. The source image is not square: 600x512."),
+            "{}",
+            message
+        );
+        let no_alpha = SourceImage::Raster(image::DynamicImage::new_rgb8(512, 512));
+        assert!(validate_source(no_alpha, b"", SourceFix::Abort).is_err());
+    }
+
+    #[test]
+    fn small_source_and_transparent_border_are_issues() {
+        // the artwork is the half in the middle
+        let small = SourceImage::Raster(image::DynamicImage::ImageRgba8(image::RgbaImage::from_fn(256, 256, |x, y| {
+            image::Rgba([0, 0, 0, if (64..192).contains(&x) && (64..192).contains(&y) { 255 } else { 0 }])
+        })));
+        assert_eq!(
+            source_issues(&small, b""),
+            vec![
+                SourceIssue::TooSmall { width: 256, height: 256 },
+                SourceIssue::TransparentBorder { content_percent: 50 }
+            ]
+        );
+    }
+
+    #[test]
+    fn svg_text_is_an_issue() {
+        let source = SourceImage::from_svg(SVG_WITH_TEXT.as_bytes()).unwrap();
+        assert_eq!(source_issues(&source, SVG_WITH_TEXT.as_bytes()), vec![SourceIssue::SvgText]);
+        let svg_without_text = SVG_WITH_TEXT.replace(r#"<text x="1" y="8">A</text>"#, "");
+        let source = SourceImage::from_svg(svg_without_text.as_bytes()).unwrap();
+        assert!(source_issues(&source, svg_without_text.as_bytes()).is_empty());
+    }
+}
@@ -0,0 +1,24 @@
+//! utils_mod.rs
+//! small pure Rust helpers, used by the generator and by the web front-end
+
+/// HTML encode - naive
+pub fn html_encode(input: &str) -> String {
+    input
+        .replace("&", "&amp;")
+        .replace("\"", "&quot;")
+        .replace("'", "&apos;")
+        .replace("<", "&lt;")
+        .replace(">", "&gt;")
+}
+
+/// content hash for cache busting, FNV-1a 64 bit as 16 hex digits.
+/// It is stable across versions and platforms, it is not meant for security.
+pub fn content_hash(content: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in content.iter() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    // return
+    format!("{:016x}", hash)
+}
@@ -0,0 +1,188 @@
+//! web_app_manifest_mod.rs
+//! Typed model of the Web App Manifest <https://www.w3.org/TR/appmanifest/>.
+//! It is serialized with serde_json, so all the strings are correctly escaped.
+//! Empty optional fields are not written into manifest.json.
+//! Pure Rust, no javascript objects here.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error_mod::HelperError;
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct WebAppManifest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub short_name: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub icons: Vec<ManifestImage>,
+    pub start_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>,
+    pub display: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub display_override: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orientation: Option<String>,
+    pub background_color: String,
+    pub theme_color: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iarc_rating_id: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub shortcuts: Vec<ManifestShortcut>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub screenshots: Vec<ManifestImage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub related_applications: Vec<RelatedApplication>,
+    #[serde(skip_serializing_if = "is_false")]
+    pub prefer_related_applications: bool,
+}
+
+/// image resource used for icons, screenshots and shortcut icons
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ManifestImage {
+    pub src: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sizes: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub form_factor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ManifestShortcut {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub short_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub icons: Vec<ManifestImage>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RelatedApplication {
+    pub platform: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+}
+
+impl WebAppManifest {
+    /// the content of manifest.json
+    pub fn to_json(&self) -> String {
+        // serialization of this simple structs cannot fail
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// serde skip_serializing_if needs a function
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// empty string is None
+pub fn non_empty(text: &str) -> Option<String> {
+    let text = text.trim();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text.to_string())
+    }
+}
+
+/// comma separated list into vector, empty items are ignored
+pub fn split_list(text: &str) -> Vec<String> {
+    text.split(',').filter_map(non_empty).collect()
+}
+
+/// parse a json array typed in a textarea. Empty text is an empty vector.
+pub fn parse_json_list<T: serde::de::DeserializeOwned>(field_name: &str, text: &str) -> Result<Vec<T>, HelperError> {
+    if text.trim().is_empty() {
+        return Ok(vec![]);
+    }
+    serde_json::from_str(text).map_err(|err| HelperError::Input(format!("Manifest {} is not a valid json array: {}", field_name, err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_and_backslashes_round_trip() {
+        let manifest = WebAppManifest {
+            short_name: r#"My "App""#.to_string(),
+            name: r#"C:\apps\"best" app"#.to_string(),
+            ..Default::default()
+        };
+        let value: serde_json::Value = serde_json::from_str(&manifest.to_json()).unwrap();
+        assert_eq!(value["short_name"], r#"My "App""#);
+        assert_eq!(value["name"], r#"C:\apps\"best" app"#);
+    }
+
+    #[test]
+    fn empty_optional_fields_are_omitted() {
+        let manifest = WebAppManifest {
+            short_name: "App".to_string(),
+            description: non_empty("  "),
+            display_override: split_list(" , "),
+            ..Default::default()
+        };
+        let value: serde_json::Value = serde_json::from_str(&manifest.to_json()).unwrap();
+        let object = value.as_object().unwrap();
+        for field in [
+            "id",
+            "description",
+            "scope",
+            "lang",
+            "dir",
+            "display_override",
+            "orientation",
+            "categories",
+            "iarc_rating_id",
+            "shortcuts",
+            "screenshots",
+            "related_applications",
+            "prefer_related_applications",
+        ]
+        .iter()
+        {
+            assert!(!object.contains_key(*field), "{}", field);
+        }
+        // the required fields are always there
+        for field in [
+            "short_name",
+            "name",
+            "icons",
+            "start_url",
+            "display",
+            "background_color",
+            "theme_color",
+        ]
+        .iter()
+        {
+            assert!(object.contains_key(*field), "{}", field);
+        }
+    }
+
+    #[test]
+    fn parse_json_list_errors_have_the_field_name() {
+        assert!(parse_json_list::<ManifestShortcut>("shortcuts", " ").unwrap().is_empty());
+        let message = parse_json_list::<ManifestShortcut>("shortcuts", "[{").unwrap_err().to_string();
+        assert!(message.contains("Manifest shortcuts is not a valid json array"), "{}", message);
+    }
+}
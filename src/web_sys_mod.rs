@@ -2,30 +2,45 @@
 //! helper functions for web_sys, window, document, dom, console,
 //! local_storage, session_storage,...
 //! Trying to isolate/hide all javascript code and conversion here.
+//! The errors are returned as HelperError, so the app can show them in the page.
 
 // region: use
-use unwrap::unwrap;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::{JsCast, JsValue};
 // use wasm_bindgen_futures::JsFuture;
 use web_sys::console;
 // use web_sys::{Request, RequestInit, Response};
+
+use crate::error_mod::HelperError;
+use crate::utils_mod::html_encode;
 // endregion: use
 
+/// javascript errors are JsValue, mostly with a string inside
+impl From<JsValue> for HelperError {
+    fn from(js_value: JsValue) -> Self {
+        HelperError::Js(js_value.as_string().unwrap_or_else(|| format!("{:?}", js_value)))
+    }
+}
+
 /// return the global window object
-pub fn window() -> web_sys::Window {
-    unwrap!(web_sys::window())
+pub fn window() -> Result<web_sys::Window, HelperError> {
+    web_sys::window().ok_or_else(|| HelperError::Js("no global window".to_string()))
 }
 
 /// return the global document object
-pub fn document() -> web_sys::Document {
-    unwrap!(window().document())
+pub fn document() -> Result<web_sys::Document, HelperError> {
+    window()?
+        .document()
+        .ok_or_else(|| HelperError::Js("no document in window".to_string()))
 }
 
 /// get element by id
-pub fn get_element_by_id(element_id: &str) -> web_sys::Element {
-    let document = unwrap!(window().document());
-    unwrap!(document.get_element_by_id(element_id))
+pub fn get_element_by_id(element_id: &str) -> Result<web_sys::Element, HelperError> {
+    document()?
+        .get_element_by_id(element_id)
+        .ok_or_else(|| HelperError::Js(format!("element {} not found", element_id)))
 }
 
 /// debug write into session_storage
@@ -34,173 +49,438 @@ pub fn debug_write(text: &str) {
     console::log_1(&JsValue::from_str(text));
 }
 
+/// the element has the expected type, for example HtmlInputElement
+fn dyn_into_element<T: JsCast>(element: web_sys::Element, element_id: &str) -> Result<T, HelperError> {
+    element
+        .dyn_into::<T>()
+        .map_err(|_| HelperError::Js(format!("element {} has not the expected type", element_id)))
+}
+
 /// get html element by id
-pub fn get_html_element_by_id(element_id: &str) -> web_sys::HtmlElement {
-    let element = get_element_by_id(element_id);
-    let html_element: web_sys::HtmlElement = unwrap!(element.dyn_into::<web_sys::HtmlElement>());
+pub fn get_html_element_by_id(element_id: &str) -> Result<web_sys::HtmlElement, HelperError> {
+    let element = get_element_by_id(element_id)?;
+    let html_element: web_sys::HtmlElement = dyn_into_element(element, element_id)?;
     //return
-    html_element
+    Ok(html_element)
 }
 
 /// get input html element by id
-pub fn get_input_html_element_by_id(element_id: &str) -> web_sys::HtmlInputElement {
-    let element = get_element_by_id(element_id);
-    let html_input_element = unwrap!(element.dyn_into::<web_sys::HtmlInputElement>());
+pub fn get_input_html_element_by_id(element_id: &str) -> Result<web_sys::HtmlInputElement, HelperError> {
+    let element = get_element_by_id(element_id)?;
+    let html_input_element = dyn_into_element(element, element_id)?;
     //return
-    html_input_element
+    Ok(html_input_element)
 }
-/// HTML encode - naive
-pub fn html_encode(input: &str) -> String {
-    input
-        .replace("&", "&amp;")
-        .replace("\"", "&quot;")
-        .replace("'", "&apos;")
-        .replace("<", "&lt;")
-        .replace(">", "&gt;")
+/// get input element value string by id
+pub fn get_input_element_value_string_by_id(element_id: &str) -> Result<String, HelperError> {
+    let input_html_element = get_input_html_element_by_id(element_id)?;
+    Ok(input_html_element.value())
 }
 
-/// get input element value string by id
-pub fn get_input_element_value_string_by_id(element_id: &str) -> String {
-    // debug_write("before get_element_by_id");
-    let input_element = get_element_by_id(element_id);
-    // debug_write("before dyn_into");
-    let input_html_element = unwrap!(input_element.dyn_into::<web_sys::HtmlInputElement>());
-    // debug_write("before value()");
-    input_html_element.value()
+/// get textarea element value string by id
+pub fn get_textarea_element_value_string_by_id(element_id: &str) -> Result<String, HelperError> {
+    let textarea_element = get_element_by_id(element_id)?;
+    let textarea_html_element: web_sys::HtmlTextAreaElement = dyn_into_element(textarea_element, element_id)?;
+    Ok(textarea_html_element.value())
+}
+
+/// get select element value string by id
+pub fn get_select_element_value_string_by_id(element_id: &str) -> Result<String, HelperError> {
+    let select_element = get_element_by_id(element_id)?;
+    let select_html_element: web_sys::HtmlSelectElement = dyn_into_element(select_element, element_id)?;
+    Ok(select_html_element.value())
+}
+
+/// get input element checked by id, for checkboxes
+pub fn get_input_element_checked_by_id(element_id: &str) -> Result<bool, HelperError> {
+    let input_html_element = get_input_html_element_by_id(element_id)?;
+    Ok(input_html_element.checked())
+}
+
+/// local storage object
+fn local_storage() -> Result<web_sys::Storage, HelperError> {
+    window()?
+        .local_storage()?
+        .ok_or_else(|| HelperError::Js("local_storage is not available".to_string()))
 }
 
 /// save to local storage
-pub fn save_to_local_storage(name: &str, value: &str) {
+pub fn save_to_local_storage(name: &str, value: &str) -> Result<(), HelperError> {
     // local_storage functions are not async. It is so much easier to use them.
-    let ls = unwrap!(unwrap!(window().local_storage()));
-    let _x = ls.set_item(name, value);
+    let ls = local_storage()?;
+    ls.set_item(name, value)?;
+    Ok(())
 }
 
 /// load string from local_storage
-pub fn load_string_from_local_storage(name: &str, default_value: &str) -> String {
+pub fn load_string_from_local_storage(name: &str, default_value: &str) -> Result<String, HelperError> {
     // local_storage functions are not async. It is so much easier to use them.
-    let ls = unwrap!(unwrap!(window().local_storage()));
+    let ls = local_storage()?;
     // return nickname
-    unwrap!(ls.get_item(name)).unwrap_or(default_value.to_string())
+    Ok(ls.get_item(name)?.unwrap_or_else(|| default_value.to_string()))
 }
 
 /// returns now as DateTime
-pub fn date_time_now() -> zip::DateTime {
+pub fn date_time_now() -> Result<zip::DateTime, HelperError> {
     let now = js_sys::Date::new_0();
-    let now = unwrap!(zip::DateTime::from_date_and_time(
+    let now = zip::DateTime::from_date_and_time(
         now.get_full_year() as u16,
         now.get_month() as u8 + 1,
         now.get_date() as u8,
         now.get_hours() as u8,
         now.get_minutes() as u8,
         now.get_seconds() as u8,
-    ));
+    )
+    .map_err(|_| HelperError::Js("the date is out of range for zip".to_string()))?;
     // return now
-    now
+    Ok(now)
+}
+
+/// set input element value by id
+pub fn set_input_element_value_by_id(element_id: &str, value: &str) -> Result<(), HelperError> {
+    let input_html_element = get_input_html_element_by_id(element_id)?;
+    input_html_element.set_value(value);
+    Ok(())
+}
+
+/// set the style attribute of the element by id
+pub fn set_style_by_id(element_id: &str, style: &str) -> Result<(), HelperError> {
+    get_element_by_id(element_id)?.set_attribute("style", style)?;
+    Ok(())
 }
 
 /// set inner html into dom
-pub fn set_inner_html(element_id: &str, inner_html: &str) {
+pub fn set_inner_html(element_id: &str, inner_html: &str) -> Result<(), HelperError> {
     // TODO: inner_html should be a wrapper around String
     // to create this wrapper the variables should be html_encoded
-    let div_for_wasm_html_injecting = get_element_by_id(element_id);
+    let div_for_wasm_html_injecting = get_element_by_id(element_id)?;
     div_for_wasm_html_injecting.set_inner_html(inner_html);
+    Ok(())
 }
 
 /// resize window
-pub fn resize_window(width: i32, height: i32) {
-    unwrap!(window().resize_to(width, height));
+pub fn resize_window(width: i32, height: i32) -> Result<(), HelperError> {
+    window()?.resize_to(width, height)?;
+    Ok(())
 }
 
 /// add event listener
+/// The errors inside the event handlers are shown in the page.
 pub fn add_listener_on_file_change_to_read_single_file(
     element_id: &str,
     fn_on_file_change: &'static (dyn Fn(Vec<u8>) + 'static),
-) {
+) -> Result<(), HelperError> {
     // prepare a clone, that is moved into the Box
     let element_id_clone = element_id.to_string();
     let handler_1 = Box::new(move || {
-        // get the file from the input file-list. I expect only one single png file.
-        let input_element = get_input_html_element_by_id(&element_id_clone);
-        let files = unwrap!(input_element.files());
-        let file = unwrap!(files.get(0));
-        let file_name = file.name();
-
-        // prepare event listener for on load end
-        let fr = web_sys::FileReader::new().unwrap();
-        let fr_c = fr.clone();
+        if let Err(err) = read_single_file(&element_id_clone, fn_on_file_change) {
+            show_error(&err);
+        }
+    }) as Box<dyn FnMut()>;
+    let closure = Closure::wrap(handler_1);
 
-        // create onLoadEnd callback
-        let handler_2 = Box::new(move |_e: web_sys::ProgressEvent| {
-            let array = js_sys::Uint8Array::new(&fr_c.result().unwrap());
-            debug_write(&format!(
-                "File {} length {} bytes",
-                file_name,
-                array.byte_length(),
-            ));
+    let html_element = get_html_element_by_id(element_id)?;
+    html_element.set_onchange(Some(closure.as_ref().unchecked_ref()));
+    closure.forget();
+    Ok(())
+}
+
+/// read the file chosen in the input element and then call fn_on_file_change
+fn read_single_file(element_id: &str, fn_on_file_change: &'static (dyn Fn(Vec<u8>) + 'static)) -> Result<(), HelperError> {
+    // get the file from the input file-list. I expect only one single png file.
+    let input_element = get_input_html_element_by_id(element_id)?;
+    let files = input_element
+        .files()
+        .ok_or_else(|| HelperError::Js("input has no file list".to_string()))?;
+    let file = match files.get(0) {
+        Some(file) => file,
+        // the user canceled the file dialog
+        None => return Ok(()),
+    };
+    let file_name = file.name();
+    // the same file can be chosen again after an error
+    input_element.set_value("");
+
+    // prepare event listener for on load end
+    let fr = web_sys::FileReader::new()?;
+    let fr_c = fr.clone();
+
+    // create onLoadEnd callback
+    let handler_2 = Box::new(move |_e: web_sys::ProgressEvent| match fr_c.result() {
+        Ok(result) => {
+            let array = js_sys::Uint8Array::new(&result);
+            debug_write(&format!("File {} length {} bytes", file_name, array.byte_length(),));
             let vec = array.to_vec();
             // finally call the method that is sent in the parameter, after the file is chosen
             fn_on_file_change(vec);
-        }) as Box<dyn FnMut(web_sys::ProgressEvent)>;
-        let onloadend_cb = Closure::wrap(handler_2);
-        fr.set_onloadend(Some(onloadend_cb.as_ref().unchecked_ref()));
+        }
+        Err(err) => show_error(&HelperError::from(err)),
+    }) as Box<dyn FnMut(web_sys::ProgressEvent)>;
+    let onloadend_cb = Closure::wrap(handler_2);
+    fr.set_onloadend(Some(onloadend_cb.as_ref().unchecked_ref()));
 
-        // read the file
-        fr.read_as_array_buffer(&file).expect("blob not readable");
-        onloadend_cb.forget();
+    // read the file
+    fr.read_as_array_buffer(&file)?;
+    onloadend_cb.forget();
+    Ok(())
+}
+
+/// add event listener on click
+/// The errors inside the event handler are shown in the page.
+pub fn add_listener_on_click(
+    element_id: &str,
+    fn_on_click: &'static (dyn Fn() -> Result<(), HelperError> + 'static),
+) -> Result<(), HelperError> {
+    let handler_1 = Box::new(move || {
+        if let Err(err) = fn_on_click() {
+            show_error(&err);
+        }
+    }) as Box<dyn FnMut()>;
+    let closure = Closure::wrap(handler_1);
+
+    let html_element = get_html_element_by_id(element_id)?;
+    html_element.set_onclick(Some(closure.as_ref().unchecked_ref()));
+    closure.forget();
+    Ok(())
+}
+
+/// the mouse drag over an element, the positions are fractions of the element from 0.0 to 1.0
+#[derive(Clone, Copy, Debug)]
+pub struct MouseDrag {
+    pub start_x: f64,
+    pub start_y: f64,
+    pub x: f64,
+    pub y: f64,
+    /// the element size in css pixels
+    pub element_width: f64,
+    pub element_height: f64,
+}
+
+/// add event listeners for the mouse drag over the element
+/// fn_on_drag is called on mouse down and on every mouse move while the button is pressed.
+/// The errors inside the event handlers are shown in the page.
+pub fn add_listener_on_mouse_drag(
+    element_id: &str,
+    fn_on_drag: &'static (dyn Fn(MouseDrag) -> Result<(), HelperError> + 'static),
+) -> Result<(), HelperError> {
+    let html_element = get_html_element_by_id(element_id)?;
+    // the start position is shared between the closures, None when the button is not pressed
+    let drag_start: Rc<Cell<Option<(f64, f64)>>> = Rc::new(Cell::new(None));
+
+    let element_clone = html_element.clone();
+    let drag_start_clone = drag_start.clone();
+    let handler_down = Box::new(move |event: web_sys::MouseEvent| {
+        // the browser must not drag the image itself
+        event.prevent_default();
+        let (x, y, width, height) = mouse_position_in_element(&element_clone, &event);
+        drag_start_clone.set(Some((x, y)));
+        if let Err(err) = fn_on_drag(MouseDrag {
+            start_x: x,
+            start_y: y,
+            x,
+            y,
+            element_width: width,
+            element_height: height,
+        }) {
+            show_error(&err);
+        }
+    }) as Box<dyn FnMut(web_sys::MouseEvent)>;
+    let closure_down = Closure::wrap(handler_down);
+    html_element.set_onmousedown(Some(closure_down.as_ref().unchecked_ref()));
+    closure_down.forget();
+
+    let element_clone = html_element.clone();
+    let drag_start_clone = drag_start.clone();
+    let handler_move = Box::new(move |event: web_sys::MouseEvent| {
+        if let Some((start_x, start_y)) = drag_start_clone.get() {
+            let (x, y, width, height) = mouse_position_in_element(&element_clone, &event);
+            if let Err(err) = fn_on_drag(MouseDrag {
+                start_x,
+                start_y,
+                x,
+                y,
+                element_width: width,
+                element_height: height,
+            }) {
+                show_error(&err);
+            }
+        }
+    }) as Box<dyn FnMut(web_sys::MouseEvent)>;
+    let closure_move = Closure::wrap(handler_move);
+    html_element.set_onmousemove(Some(closure_move.as_ref().unchecked_ref()));
+    closure_move.forget();
+
+    // the drag ends when the button is released or the mouse leaves the element
+    let drag_start_clone = drag_start;
+    let handler_up = Box::new(move |_event: web_sys::MouseEvent| {
+        drag_start_clone.set(None);
+    }) as Box<dyn FnMut(web_sys::MouseEvent)>;
+    let closure_up = Closure::wrap(handler_up);
+    html_element.set_onmouseup(Some(closure_up.as_ref().unchecked_ref()));
+    html_element.set_onmouseleave(Some(closure_up.as_ref().unchecked_ref()));
+    closure_up.forget();
+    Ok(())
+}
+
+/// the mouse position as fractions of the element and the element size in css pixels
+fn mouse_position_in_element(element: &web_sys::HtmlElement, event: &web_sys::MouseEvent) -> (f64, f64, f64, f64) {
+    let rect = element.get_bounding_client_rect();
+    let (width, height) = (rect.width().max(1.0), rect.height().max(1.0));
+    let x = ((event.client_x() as f64 - rect.left()) / width).clamp(0.0, 1.0);
+    let y = ((event.client_y() as f64 - rect.top()) / height).clamp(0.0, 1.0);
+    // return
+    (x, y, width, height)
+}
+
+/// add event listener for an input with the attribute multiple
+/// fn_on_files_change is called once, when all the chosen files are read.
+/// The errors inside the event handlers are shown in the page.
+pub fn add_listener_on_file_change_to_read_multiple_files(
+    element_id: &str,
+    fn_on_files_change: &'static (dyn Fn(Vec<Vec<u8>>) + 'static),
+) -> Result<(), HelperError> {
+    let element_id_clone = element_id.to_string();
+    let handler_1 = Box::new(move || {
+        if let Err(err) = read_multiple_files(&element_id_clone, fn_on_files_change) {
+            show_error(&err);
+        }
     }) as Box<dyn FnMut()>;
     let closure = Closure::wrap(handler_1);
 
-    let html_element = get_html_element_by_id(element_id);
+    let html_element = get_html_element_by_id(element_id)?;
     html_element.set_onchange(Some(closure.as_ref().unchecked_ref()));
     closure.forget();
+    Ok(())
+}
+
+/// read all the files chosen in the input element and then call fn_on_files_change
+fn read_multiple_files(element_id: &str, fn_on_files_change: &'static (dyn Fn(Vec<Vec<u8>>) + 'static)) -> Result<(), HelperError> {
+    let input_element = get_input_html_element_by_id(element_id)?;
+    let files = input_element
+        .files()
+        .ok_or_else(|| HelperError::Js("input has no file list".to_string()))?;
+    let files_count = files.length() as usize;
+    if files_count == 0 {
+        // the user canceled the file dialog
+        return Ok(());
+    }
+    // the file readers end in any order, the last one calls fn_on_files_change
+    let results: Rc<RefCell<Vec<Vec<u8>>>> = Rc::new(RefCell::new(vec![]));
+    for i in 0..files.length() {
+        let file = files
+            .get(i)
+            .ok_or_else(|| HelperError::Js(format!("file {} not found in the file list", i)))?;
+        let fr = web_sys::FileReader::new()?;
+        let fr_c = fr.clone();
+        let results_clone = results.clone();
+        let handler_2 = Box::new(move |_e: web_sys::ProgressEvent| match fr_c.result() {
+            Ok(result) => {
+                let vec = js_sys::Uint8Array::new(&result).to_vec();
+                let all_read = {
+                    let mut results = results_clone.borrow_mut();
+                    results.push(vec);
+                    results.len() == files_count
+                };
+                if all_read {
+                    fn_on_files_change(results_clone.replace(vec![]));
+                }
+            }
+            Err(err) => show_error(&HelperError::from(err)),
+        }) as Box<dyn FnMut(web_sys::ProgressEvent)>;
+        let onloadend_cb = Closure::wrap(handler_2);
+        fr.set_onloadend(Some(onloadend_cb.as_ref().unchecked_ref()));
+        fr.read_as_array_buffer(&file)?;
+        onloadend_cb.forget();
+    }
+    Ok(())
 }
 
 /// append anchor for file url
-pub fn append_anchor_for_file_url(url: &str, file_name: &str) {
+pub fn append_anchor_for_file_url(url: &str, file_name: &str) -> Result<(), HelperError> {
     debug_write(&format!("append_anchor_for_file_url: {}", url));
-    let p = unwrap!(document().create_element("p"));
+    let p = document()?.create_element("p")?;
 
-    let anchor = unwrap!(document().create_element("a"));
-    let anchor: web_sys::HtmlAnchorElement =
-        unwrap!(anchor.dyn_into::<web_sys::HtmlAnchorElement>());
+    let anchor = document()?.create_element("a")?;
+    let anchor: web_sys::HtmlAnchorElement = dyn_into_element(anchor, "a")?;
     anchor.set_href(url);
     anchor.set_download(file_name);
-    unwrap!(anchor.set_text(&format!(
-        "click here to download: {}",
-        html_encode(&file_name)
-    )));
-    let div_for_wasm_html_injecting = get_element_by_id("div_for_wasm_html_injecting");
-    let div_for_wasm_html_injecting =
-        unwrap!(div_for_wasm_html_injecting.dyn_into::<web_sys::Node>());
-    let anchor = unwrap!(anchor.dyn_into::<web_sys::Node>());
-    unwrap!(p.append_child(&anchor));
-    unwrap!(div_for_wasm_html_injecting.append_child(&p));
+    anchor.set_text(&format!("click here to download: {}", html_encode(file_name)))?;
+    p.append_child(&anchor)?;
+    get_element_by_id("div_result")?.append_child(&p)?;
+    Ok(())
 }
 
 /// append the final comment
-pub fn append_final_comment(comment: &str) {
-    let p: web_sys::Element = unwrap!(document().create_element("p"));
+pub fn append_final_comment(comment: &str) -> Result<(), HelperError> {
+    let p: web_sys::Element = document()?.create_element("p")?;
     p.set_text_content(Some(comment));
+    get_element_by_id("div_result")?.append_child(&p)?;
+    Ok(())
+}
+
+/// show the error as a readable message in the result of the generation, it is cleared by the next generation.
+/// The inputs stay in the page, so the user can correct them and try again.
+pub fn show_error(err: &HelperError) {
+    debug_write(&format!("error: {}", err));
+    // before the html is injected there is no div_result
+    let element_id = match document().map(|document| document.get_element_by_id("div_result")) {
+        Ok(Some(_)) => "div_result",
+        _ => "div_for_wasm_html_injecting",
+    };
+    // if even the error cannot be shown in the page, the console is the last resort
+    if let Err(err) = append_paragraph_with_class(element_id, &err.to_string(), "error") {
+        console::error_1(&JsValue::from_str(&err.to_string()));
+    }
+}
+
+/// show a warning in the result of the generation, the generation continues
+pub fn show_warning(text: &str) -> Result<(), HelperError> {
+    append_paragraph_with_class("div_result", text, "warning")
+}
 
-    let div_for_wasm_html_injecting = get_element_by_id("div_for_wasm_html_injecting");
-    let div_for_wasm_html_injecting =
-        unwrap!(div_for_wasm_html_injecting.dyn_into::<web_sys::Node>());
-    unwrap!(div_for_wasm_html_injecting.append_child(&p));
+/// append a paragraph with a css class into the element
+fn append_paragraph_with_class(element_id: &str, text: &str, class_name: &str) -> Result<(), HelperError> {
+    let p: web_sys::Element = document()?.create_element("p")?;
+    p.set_class_name(class_name);
+    p.set_text_content(Some(text));
+    get_element_by_id(element_id)?.append_child(&p)?;
+    Ok(())
 }
 
 /// create download url
-pub fn create_download_url(vec_u8: Vec<u8>) -> String {
-    debug_write(&format!("create buffer"));
+pub fn create_download_url(vec_u8: Vec<u8>) -> Result<String, HelperError> {
+    debug_write("create buffer");
     let buffer = js_sys::Uint8Array::from(vec_u8.as_ref());
     let buffer_val: &wasm_bindgen::JsValue = buffer.as_ref();
     let parts = js_sys::Array::new_with_length(1);
     parts.set(0, buffer_val.clone());
-    debug_write(&format!("create blob"));
-    let blob = unwrap!(web_sys::Blob::new_with_u8_array_sequence(parts.as_ref())
-        .map_err(|_| format!("failed to create blob")));
+    debug_write("create blob");
+    let blob = web_sys::Blob::new_with_u8_array_sequence(parts.as_ref())?;
 
-    let url = unwrap!(web_sys::Url::create_object_url_with_blob(&blob));
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+    // return
+    Ok(url)
+}
+
+/// the object urls keep the blobs in memory until they are revoked
+pub fn revoke_object_urls(urls: &[String]) -> Result<(), HelperError> {
+    for url in urls.iter() {
+        web_sys::Url::revoke_object_url(url)?;
+    }
+    Ok(())
+}
+
+/// create object url with the mime type, the img element needs it to show a svg
+pub fn create_object_url_with_type(vec_u8: &[u8], mime_type: &str) -> Result<String, HelperError> {
+    let buffer = js_sys::Uint8Array::from(vec_u8);
+    let parts = js_sys::Array::new_with_length(1);
+    parts.set(0, buffer.into());
+    // the setter of BlobPropertyBag has a different name in the newer web-sys, a plain object works with every version
+    let options = js_sys::Object::new();
+    js_sys::Reflect::set(&options, &JsValue::from_str("type"), &JsValue::from_str(mime_type))?;
+    let options: web_sys::BlobPropertyBag = options.unchecked_into();
+    let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(parts.as_ref(), &options)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
     // return
-    url
+    Ok(url)
 }
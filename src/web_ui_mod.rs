@@ -0,0 +1,748 @@
+//! web_ui_mod.rs
+//! The wasm front-end is a thin shell around the pure Rust generator in prepare_zip_mod.rs.
+//! It injects the html form, reads and saves the inputs in local_storage,
+//! calls the generator and shows the preview and the download link.
+//! All the javascript objects, functions and conversion are in web_sys_mod.rs.
+
+use crate::error_mod::HelperError;
+use crate::icon_set_mod::*;
+use crate::image_processing_mod::CropRect;
+use crate::png_optimization_mod::*;
+use crate::prepare_zip_mod::*;
+use crate::utils_mod::html_encode;
+use crate::web_sys_mod::*;
+use std::cell::RefCell;
+
+thread_local! {
+    /// the bytes of the last selected source file, the crop editor generates again from them
+    static SOURCE_BYTES: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    /// the hand-tuned small sources, they stay for the next source file
+    static SMALL_SOURCES_BYTES: RefCell<Vec<Vec<u8>>> = const { RefCell::new(Vec::new()) };
+    /// the object url of the image in the crop editor, revoked when the next source file is selected
+    static CROP_IMAGE_URL: RefCell<Option<String>> = const { RefCell::new(None) };
+    /// the object urls of the previews and of the zip, revoked before the next generation
+    static GENERATED_URLS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The app starts with this function
+pub fn start_function() -> Result<(), HelperError> {
+    // Initialize debugging for when/if something goes wrong.
+    console_error_panic_hook::set_once();
+    // write the app version just for debug purposes
+    debug_write(&format!("rust_wasm_helper_for_pwa v{}", env!("CARGO_PKG_VERSION")));
+    // set the window initial size
+    resize_window(800, 600)?;
+    // load from local storage
+    let data = load_all_from_local_storage()?;
+    // inject html into DOM
+    inject_htm_into_dom(&data)?;
+    // prepare events that read local file, pass the function to execute
+    add_listener_on_file_change_to_read_single_file("file_input", &on_file_change)?;
+    add_listener_on_file_change_to_read_multiple_files("file_input_small", &on_small_files_change)?;
+    add_listener_on_click("clear_small_sources", &on_clear_small_sources)?;
+    Ok(())
+}
+
+/// load all from local storage
+pub fn load_all_from_local_storage() -> Result<PwaData, HelperError> {
+    let default = PwaData::default();
+    let data = PwaData {
+        pwa_short_name: load_string_from_local_storage("pwa_short_name", &default.pwa_short_name)?,
+        pwa_name: load_string_from_local_storage("pwa_name", &default.pwa_name)?,
+        pwa_description: load_string_from_local_storage("pwa_description", &default.pwa_description)?,
+        pwa_folder: load_string_from_local_storage("pwa_folder", &default.pwa_folder)?,
+        pwa_icon_set: load_string_from_local_storage("pwa_icon_set", &default.pwa_icon_set)?,
+        pwa_resize_filter: load_string_from_local_storage("pwa_resize_filter", &default.pwa_resize_filter)?,
+        pwa_linear_light: load_string_from_local_storage("pwa_linear_light", &default.pwa_linear_light.to_string())? == "true",
+        pwa_sharpen: load_string_from_local_storage("pwa_sharpen", &default.pwa_sharpen)?,
+        pwa_png_optimization: load_string_from_local_storage("pwa_png_optimization", &default.pwa_png_optimization.to_string())? == "true",
+        pwa_webp: load_string_from_local_storage("pwa_webp", &default.pwa_webp.to_string())? == "true",
+        pwa_avif: load_string_from_local_storage("pwa_avif", &default.pwa_avif.to_string())? == "true",
+        pwa_crop_rect: load_string_from_local_storage("pwa_crop_rect", &default.pwa_crop_rect)?,
+        pwa_source_fix: load_string_from_local_storage("pwa_source_fix", &default.pwa_source_fix)?,
+        pwa_maskable_background: load_string_from_local_storage("pwa_maskable_background", &default.pwa_maskable_background)?,
+        pwa_image_compression: load_string_from_local_storage("pwa_image_compression", &default.pwa_image_compression)?,
+        pwa_id: load_string_from_local_storage("pwa_id", &default.pwa_id)?,
+        pwa_start_url: load_string_from_local_storage("pwa_start_url", &default.pwa_start_url)?,
+        pwa_scope: load_string_from_local_storage("pwa_scope", &default.pwa_scope)?,
+        pwa_lang: load_string_from_local_storage("pwa_lang", &default.pwa_lang)?,
+        pwa_dir: load_string_from_local_storage("pwa_dir", &default.pwa_dir)?,
+        pwa_display: load_string_from_local_storage("pwa_display", &default.pwa_display)?,
+        pwa_display_override: load_string_from_local_storage("pwa_display_override", &default.pwa_display_override)?,
+        pwa_orientation: load_string_from_local_storage("pwa_orientation", &default.pwa_orientation)?,
+        pwa_background_color: load_string_from_local_storage("pwa_background_color", &default.pwa_background_color)?,
+        pwa_theme_color: load_string_from_local_storage("pwa_theme_color", &default.pwa_theme_color)?,
+        pwa_categories: load_string_from_local_storage("pwa_categories", &default.pwa_categories)?,
+        pwa_iarc_rating_id: load_string_from_local_storage("pwa_iarc_rating_id", &default.pwa_iarc_rating_id)?,
+        pwa_shortcuts: load_string_from_local_storage("pwa_shortcuts", &default.pwa_shortcuts)?,
+        pwa_screenshots: load_string_from_local_storage("pwa_screenshots", &default.pwa_screenshots)?,
+        pwa_related_applications: load_string_from_local_storage("pwa_related_applications", &default.pwa_related_applications)?,
+        pwa_prefer_related_applications: load_string_from_local_storage(
+            "pwa_prefer_related_applications",
+            &default.pwa_prefer_related_applications.to_string(),
+        )? == "true",
+        pwa_caching_rules: load_string_from_local_storage("pwa_caching_rules", &default.pwa_caching_rules)?,
+        pwa_precache_extra: load_string_from_local_storage("pwa_precache_extra", &default.pwa_precache_extra)?,
+        pwa_offline_page: load_string_from_local_storage("pwa_offline_page", &default.pwa_offline_page.to_string())? == "true",
+        pwa_cache_version: load_string_from_local_storage("pwa_cache_version", &default.pwa_cache_version)?,
+        pwa_apple_splash: load_string_from_local_storage("pwa_apple_splash", &default.pwa_apple_splash.to_string())? == "true",
+        pwa_install_button: load_string_from_local_storage("pwa_install_button", &default.pwa_install_button.to_string())? == "true",
+        pwa_update_flow: load_string_from_local_storage("pwa_update_flow", &default.pwa_update_flow)?,
+        pwa_update_banner_text: load_string_from_local_storage("pwa_update_banner_text", &default.pwa_update_banner_text)?,
+    };
+    // return
+    Ok(data)
+}
+
+/// inject html into dom
+pub fn inject_htm_into_dom(pwa_data: &PwaData) -> Result<(), HelperError> {
+    // rust has `Raw string literals` that are great!
+    // just add r# before and # after the start and end double quotes.
+    let html = format!(
+        r##"
+        <h2>Helper for PWA</h2>
+		<p>Creates a minimal working PWA that can be than copied to your project.
+		All the processing is done inside your browser with wasm.
+        No data is transferred over the net.</p>
+		<p>First enter this basic info:</p>
+		<div class="button-wrap">
+            <label for="pwa_short_name">PWA short name:</label>  
+            <input style="width:20%;" type="text" id="pwa_short_name" value="{}"/>
+        </div>
+		<div class="button-wrap">
+            <label for="pwa_name">PWA name:</label>  
+            <input style="width:40%;"  type="text" id="pwa_name" value="{}"/>
+        </div>
+		<div class="button-wrap">
+            <label for="pwa_description">PWA description:</label>  
+            <input style="width:80%;" type="text" id="pwa_description" value="{}"/>
+        </div>
+        <div class="button-wrap">
+            <label for="pwa_folder">PWA folder name:</label>  
+            <input style="width:40%;" type="text" id="pwa_folder" value="{}"/>
+        </div>
+        <p>The icon set, one icon per line: size file_name purpose(any, maskable) platforms(favicon, shortcut, ios, manifest, windows)</p>
+        <div class="button-wrap">
+            <textarea style="width:60%;" rows="17" id="pwa_icon_set">{}</textarea>
+        </div>
+        {}
+        {}
+        <p>Web app manifest (empty fields are not written into manifest.json):</p>
+        {}
+        {}
+        {}
+        <p>Service worker:</p>
+        {}
+        {}
+        {}
+        {}
+        {}
+        {}
+        {}
+        {}
+        {}
+        <p>Optional: hand-tuned square png for the small sizes (e.g. 16, 32, 48).
+        Every icon and the favicon.ico use the nearest larger source.</p>
+        <div class="button-wrap">
+            <label class="button" for="file_input_small">Select small files</label>
+            <input type="file" id="file_input_small" accept="image/png,image/svg+xml" multiple/>
+            <label class="button" id="clear_small_sources">Clear small files</label>
+        </div>
+        <p id="small_sources_info"></p>
+        <p>To create a bunch of png of different sizes,
+		select the png file at least 512x512 or bigger, or a svg file.</p>
+        
+        <!--tricky div+label+css to change Input file appearance -->
+        <div class="button-wrap">
+            <label class="button" for="file_input">Select File</label>
+            <!--only one single png or svg file. No "multiple". The event listeners are added in Rust code.-->  
+            <input type="file" id="file_input" accept="image/png,image/svg+xml"/>
+        </div>
+        <div id="div_crop"></div>
+        <div id="div_preview"></div>
+        <div id="div_result"></div>
+        "##,
+        html_encode(&pwa_data.pwa_short_name),
+        html_encode(&pwa_data.pwa_name),
+        html_encode(&pwa_data.pwa_description),
+        html_encode(&pwa_data.pwa_folder),
+        html_encode(&pwa_data.pwa_icon_set),
+        html_input_text(
+            "pwa_maskable_background",
+            "maskable icon background (#rrggbb or transparent):",
+            10,
+            &pwa_data.pwa_maskable_background
+        ),
+        html_select(
+            "pwa_image_compression",
+            "compression of png and ico in the zip (text files are always deflated):",
+            &["stored", "deflate"],
+            &pwa_data.pwa_image_compression
+        ),
+        html_for_manifest_fields(pwa_data),
+        html_checkbox(
+            "pwa_apple_splash",
+            "splash images for iOS, the icon on the background_color (34 big png files)",
+            pwa_data.pwa_apple_splash
+        ),
+        html_checkbox(
+            "pwa_install_button",
+            "install button and iOS instructions in index.html",
+            pwa_data.pwa_install_button
+        ),
+        html_for_service_worker_fields(pwa_data),
+        html_select(
+            "pwa_source_fix",
+            "a non-square source image is: pad (to square), crop (to square), abort (on any issue):",
+            &["pad", "crop", "abort"],
+            &pwa_data.pwa_source_fix
+        ),
+        html_input_text(
+            "pwa_crop_rect",
+            "crop rectangle applied before every resize: x y width height as fractions 0..1 (empty is no crop, drag over the image to set it):",
+            30,
+            &pwa_data.pwa_crop_rect
+        ),
+        html_select(
+            "pwa_resize_filter",
+            "resize filter, pixel-art scales by integer factors with padding, for crisp retro icons:",
+            &["nearest", "triangle", "catmull-rom", "gaussian", "lanczos3", "pixel-art"],
+            &pwa_data.pwa_resize_filter
+        ),
+        html_checkbox(
+            "pwa_linear_light",
+            "resize in linear light, the edges of the downscaled icons are not darkened",
+            pwa_data.pwa_linear_light
+        ),
+        html_input_text(
+            "pwa_sharpen",
+            "sharpen the icons below 64px, strength from 0 (off) to 3:",
+            10,
+            &pwa_data.pwa_sharpen
+        ),
+        html_checkbox(
+            "pwa_png_optimization",
+            "optimize the png: palette, best filter, max compression, no metadata (slower)",
+            pwa_data.pwa_png_optimization
+        ),
+        html_checkbox(
+            "pwa_webp",
+            "lossless webp variants of the manifest icons, the png stays as fallback",
+            pwa_data.pwa_webp
+        ),
+        // the wasm is built without the cargo feature avif, the encoder would make it much bigger
+        if cfg!(feature = "avif") {
+            html_checkbox("pwa_avif", "lossy avif variants of the manifest icons (slow)", pwa_data.pwa_avif)
+        } else {
+            html_checkbox_disabled("pwa_avif", "lossy avif variants of the manifest icons (only in the CLI)")
+        }
+    );
+
+    set_inner_html("div_for_wasm_html_injecting", &html)
+}
+
+/// html inputs for the web app manifest fields
+fn html_for_manifest_fields(pwa_data: &PwaData) -> String {
+    let mut html = String::new();
+    html.push_str(&html_input_text("pwa_id", "id:", 40, &pwa_data.pwa_id));
+    html.push_str(&html_input_text(
+        "pwa_start_url",
+        "start_url (default /folder/index.html):",
+        40,
+        &pwa_data.pwa_start_url,
+    ));
+    html.push_str(&html_input_text("pwa_scope", "scope:", 40, &pwa_data.pwa_scope));
+    html.push_str(&html_input_text("pwa_lang", "lang:", 10, &pwa_data.pwa_lang));
+    html.push_str(&html_select("pwa_dir", "dir:", &["", "auto", "ltr", "rtl"], &pwa_data.pwa_dir));
+    html.push_str(&html_select(
+        "pwa_display",
+        "display:",
+        &["fullscreen", "standalone", "minimal-ui", "browser"],
+        &pwa_data.pwa_display,
+    ));
+    html.push_str(&html_input_text(
+        "pwa_display_override",
+        "display_override (comma separated):",
+        40,
+        &pwa_data.pwa_display_override,
+    ));
+    html.push_str(&html_select(
+        "pwa_orientation",
+        "orientation:",
+        &[
+            "",
+            "any",
+            "natural",
+            "landscape",
+            "landscape-primary",
+            "landscape-secondary",
+            "portrait",
+            "portrait-primary",
+            "portrait-secondary",
+        ],
+        &pwa_data.pwa_orientation,
+    ));
+    html.push_str(&html_input_text(
+        "pwa_background_color",
+        "background_color:",
+        10,
+        &pwa_data.pwa_background_color,
+    ));
+    html.push_str(&html_input_text("pwa_theme_color", "theme_color:", 10, &pwa_data.pwa_theme_color));
+    html.push_str(&html_input_text(
+        "pwa_categories",
+        "categories (comma separated):",
+        40,
+        &pwa_data.pwa_categories,
+    ));
+    html.push_str(&html_input_text(
+        "pwa_iarc_rating_id",
+        "iarc_rating_id:",
+        40,
+        &pwa_data.pwa_iarc_rating_id,
+    ));
+    html.push_str(&html_textarea(
+        "pwa_shortcuts",
+        r#"shortcuts (json array, example: [{"name": "Open", "url": "/folder/open.html"}]):"#,
+        4,
+        &pwa_data.pwa_shortcuts,
+    ));
+    html.push_str(&html_textarea(
+        "pwa_screenshots",
+        r#"screenshots (json array, example: [{"src": "screenshot.png", "sizes": "1280x720", "type": "image/png"}]):"#,
+        4,
+        &pwa_data.pwa_screenshots,
+    ));
+    html.push_str(&html_textarea(
+        "pwa_related_applications",
+        r#"related_applications (json array, example: [{"platform": "play", "id": "com.example.app"}]):"#,
+        4,
+        &pwa_data.pwa_related_applications,
+    ));
+    html.push_str(&html_checkbox(
+        "pwa_prefer_related_applications",
+        "prefer_related_applications",
+        pwa_data.pwa_prefer_related_applications,
+    ));
+    // return
+    html
+}
+
+/// html inputs for the generated service worker
+fn html_for_service_worker_fields(pwa_data: &PwaData) -> String {
+    let mut html = String::new();
+    html.push_str(&html_textarea(
+        "pwa_caching_rules",
+        "caching rules, one per line: pattern (navigation, *.html, pkg/*.wasm, **) strategy (cache-first, network-first, stale-while-revalidate, network-only, cache-only):",
+        6,
+        &pwa_data.pwa_caching_rules,
+    ));
+    html.push_str(&html_textarea(
+        "pwa_precache_extra",
+        "all the files in the zip are precached, add here other paths, one per line (example: pkg/app_bg.wasm):",
+        4,
+        &pwa_data.pwa_precache_extra,
+    ));
+    html.push_str(&html_checkbox(
+        "pwa_offline_page",
+        "generate offline.html as the fallback page when offline",
+        pwa_data.pwa_offline_page,
+    ));
+    html.push_str(&html_select(
+        "pwa_cache_version",
+        "CACHE_NAME version from the date or from the content hash of the precached files:",
+        &["date", "content-hash"],
+        &pwa_data.pwa_cache_version,
+    ));
+    html.push_str(&html_select(
+        "pwa_update_flow",
+        "new version: reload immediately or wait for a click on the update banner:",
+        &["reload", "banner"],
+        &pwa_data.pwa_update_flow,
+    ));
+    html.push_str(&html_input_text(
+        "pwa_update_banner_text",
+        "update banner text:",
+        40,
+        &pwa_data.pwa_update_banner_text,
+    ));
+    // return
+    html
+}
+
+/// html for a labeled input text
+fn html_input_text(id: &str, label: &str, width_percent: u32, value: &str) -> String {
+    format!(
+        r##"
+        <div class="button-wrap">
+            <label for="{0}">{1}</label>
+            <input style="width:{2}%;" type="text" id="{0}" value="{3}"/>
+        </div>"##,
+        id,
+        html_encode(label),
+        width_percent,
+        html_encode(value)
+    )
+}
+
+/// html for a labeled textarea
+fn html_textarea(id: &str, label: &str, rows: u32, value: &str) -> String {
+    format!(
+        r##"
+        <div class="button-wrap">
+            <label for="{0}">{1}</label><br/>
+            <textarea style="width:60%;" rows="{2}" id="{0}">{3}</textarea>
+        </div>"##,
+        id,
+        html_encode(label),
+        rows,
+        html_encode(value)
+    )
+}
+
+/// html for a labeled select, the empty option means "not defined"
+fn html_select(id: &str, label: &str, options: &[&str], value: &str) -> String {
+    let mut html_options = String::new();
+    for option in options.iter() {
+        html_options.push_str(&format!(
+            r#"<option value="{0}"{1}>{0}</option>"#,
+            html_encode(option),
+            if *option == value { " selected" } else { "" }
+        ));
+    }
+    format!(
+        r##"
+        <div class="button-wrap">
+            <label for="{0}">{1}</label>
+            <select id="{0}">{2}</select>
+        </div>"##,
+        id,
+        html_encode(label),
+        html_options
+    )
+}
+
+/// html for a labeled checkbox
+fn html_checkbox(id: &str, label: &str, checked: bool) -> String {
+    format!(
+        r##"
+        <div class="button-wrap">
+            <input type="checkbox" id="{0}"{2}/>
+            <label for="{0}">{1}</label>
+        </div>"##,
+        id,
+        html_encode(label),
+        if checked { " checked" } else { "" }
+    )
+}
+
+/// a disabled checkbox is never checked, the option is not available in this build
+fn html_checkbox_disabled(id: &str, label: &str) -> String {
+    format!(
+        r##"
+        <div class="button-wrap">
+            <input type="checkbox" id="{0}" disabled/>
+            <label for="{0}">{1}</label>
+        </div>"##,
+        id,
+        html_encode(label)
+    )
+}
+
+/// on file change code that is not boilerplate
+/// The errors are shown in the page and the app stays usable for another attempt.
+pub fn on_file_change(vec: Vec<u8>) {
+    // the crop rectangle of the previous file does not fit the new file
+    let result = set_input_element_value_by_id("pwa_crop_rect", "")
+        .and_then(|_| show_crop_editor(&vec))
+        .and_then(|_| generate_zip(vec));
+    if let Err(err) = result {
+        show_error(&err);
+    }
+}
+
+/// the small sources are kept for the next generation, if the main source is already selected it generates again
+pub fn on_small_files_change(vecs: Vec<Vec<u8>>) {
+    let info = format!("{} small source files selected.", vecs.len());
+    SMALL_SOURCES_BYTES.with(|small_sources_bytes| *small_sources_bytes.borrow_mut() = vecs);
+    let vec = SOURCE_BYTES.with(|source_bytes| source_bytes.borrow().clone());
+    let result =
+        set_inner_html("small_sources_info", &html_encode(&info)).and_then(|_| if vec.is_empty() { Ok(()) } else { generate_zip(vec) });
+    if let Err(err) = result {
+        show_error(&err);
+    }
+}
+
+/// the file dialog cannot unselect files, the button removes the small sources and generates again without them
+fn on_clear_small_sources() -> Result<(), HelperError> {
+    set_input_element_value_by_id("file_input_small", "")?;
+    on_small_files_change(vec![]);
+    Ok(())
+}
+
+/// the uploaded image with the square selection, dragging the mouse over the image draws the selection
+fn show_crop_editor(vec: &[u8]) -> Result<(), HelperError> {
+    let mime_type = if is_svg(vec) { "image/svg+xml" } else { "image/png" };
+    let url = create_object_url_with_type(vec, mime_type)?;
+    if let Some(old_url) = CROP_IMAGE_URL.with(|crop_image_url| crop_image_url.borrow_mut().replace(url.clone())) {
+        revoke_object_urls(&[old_url])?;
+    }
+    SOURCE_BYTES.with(|source_bytes| *source_bytes.borrow_mut() = vec.to_vec());
+    set_inner_html(
+        "div_crop",
+        &format!(
+            r##"<p>Drag a square over the image to crop it, then generate again:</p>
+        <div class="crop_container" id="crop_container">
+            <img src="{}" draggable="false"/>
+            <div class="crop_selection" id="crop_selection"></div>
+        </div>
+        <div class="button-wrap">
+            <label class="button" id="crop_generate">Crop and generate again</label>
+        </div>"##,
+            url
+        ),
+    )?;
+    add_listener_on_mouse_drag("crop_container", &on_crop_drag)?;
+    add_listener_on_click("crop_generate", &on_crop_generate)?;
+    show_crop_selection(CropRect::parse(&get_input_element_value_string_by_id("pwa_crop_rect")?)?)
+}
+
+/// the square selection in display pixels, from the drag start in the direction of the mouse and clamped to the image
+fn on_crop_drag(drag: MouseDrag) -> Result<(), HelperError> {
+    let (width, height) = (drag.element_width, drag.element_height);
+    let (start_x, start_y) = (drag.start_x * width, drag.start_y * height);
+    let (delta_x, delta_y) = ((drag.x - drag.start_x) * width, (drag.y - drag.start_y) * height);
+    let space_x = if delta_x >= 0.0 { width - start_x } else { start_x };
+    let space_y = if delta_y >= 0.0 { height - start_y } else { start_y };
+    let side = delta_x.abs().max(delta_y.abs()).min(space_x).min(space_y);
+    // a click without a drag does not change the selection
+    if side < 2.0 {
+        return Ok(());
+    }
+    let left = if delta_x >= 0.0 { start_x } else { start_x - side };
+    let top = if delta_y >= 0.0 { start_y } else { start_y - side };
+    let rect = CropRect {
+        x: left / width,
+        y: top / height,
+        width: side / width,
+        height: side / height,
+    };
+    set_input_element_value_by_id("pwa_crop_rect", &rect.to_text())?;
+    show_crop_selection(Some(rect))
+}
+
+/// position the selection over the image in percent, so it does not depend on the displayed size
+fn show_crop_selection(rect: Option<CropRect>) -> Result<(), HelperError> {
+    match rect {
+        Some(rect) => set_style_by_id(
+            "crop_selection",
+            &format!(
+                "left:{:.2}%;top:{:.2}%;width:{:.2}%;height:{:.2}%;",
+                rect.x * 100.0,
+                rect.y * 100.0,
+                rect.width * 100.0,
+                rect.height * 100.0
+            ),
+        ),
+        None => set_style_by_id("crop_selection", "display:none;"),
+    }
+}
+
+/// generate again from the same source bytes with the new crop rectangle
+fn on_crop_generate() -> Result<(), HelperError> {
+    let vec = SOURCE_BYTES.with(|source_bytes| source_bytes.borrow().clone());
+    show_crop_selection(CropRect::parse(&get_input_element_value_string_by_id("pwa_crop_rect")?)?)?;
+    generate_zip(vec)
+}
+
+/// generate all the files in the pure Rust prepare_zip_mod, then show the preview and the download link
+pub fn generate_zip(vec: Vec<u8>) -> Result<(), HelperError> {
+    // the result of the previous generation is replaced, also the old errors
+    set_inner_html("div_preview", "")?;
+    set_inner_html("div_result", "")?;
+    let old_urls = GENERATED_URLS.with(|generated_urls| std::mem::take(&mut *generated_urls.borrow_mut()));
+    revoke_object_urls(&old_urls)?;
+
+    // get date time now
+    let now = date_time_now()?;
+
+    // save Input Text elements to local storage
+    let pwa_data = read_input_elements_and_save_to_local_storage()?;
+    let small_sources_bytes = SMALL_SOURCES_BYTES.with(|small_sources_bytes| small_sources_bytes.borrow().clone());
+    let generated = generate_pwa_zip(&pwa_data, &vec, &small_sources_bytes, now)?;
+    for warning in generated.warnings.iter() {
+        show_warning(warning)?;
+    }
+
+    let mut previews = vec![];
+    for icon in generated.icons.iter() {
+        previews.push((&icon.entry, create_generated_url(icon.png.clone())?));
+    }
+    let mut html = html_for_preview(&previews);
+    if !generated.png_sizes.is_empty() {
+        html.push_str(&html_for_png_report(&generated.png_sizes));
+    }
+    set_inner_html("div_preview", &html)?;
+
+    let url = create_generated_url(generated.zip)?;
+    append_anchor_for_file_url(&url, "pwa_minimal_files.zip")?;
+    append_final_comment(
+        "Extract the zip files to a web site that has https. The files must be inside the defined folder and not on the website root.",
+    )?;
+    Ok(())
+}
+
+/// the url is remembered, so it is revoked before the next generation
+fn create_generated_url(vec_u8: Vec<u8>) -> Result<String, HelperError> {
+    let url = create_download_url(vec_u8)?;
+    GENERATED_URLS.with(|generated_urls| generated_urls.borrow_mut().push(url.clone()));
+    // return
+    Ok(url)
+}
+
+/// preview of all generated icons in real size and the biggest maskable icon under the platform masks
+pub fn html_for_preview(previews: &[(&IconEntry, String)]) -> String {
+    let mut html = String::from(r#"<p>Preview of the icons in real size:</p><div class="preview">"#);
+    for (entry, url) in previews.iter() {
+        html.push_str(&format!(
+            r#"<figure><img src="{0}" width="{1}" height="{1}"/><figcaption>{2}</figcaption></figure>"#,
+            url,
+            entry.size,
+            html_encode(&entry.file_name)
+        ));
+    }
+    html.push_str("</div>");
+
+    let maskable = previews
+        .iter()
+        .filter(|(entry, _url)| entry.purpose == IconPurpose::Maskable)
+        .max_by_key(|(entry, _url)| entry.size);
+    if let Some((entry, url)) = maskable {
+        html.push_str(&format!(
+            r#"<p>Maskable icon {} under the platform masks:</p><div class="preview">"#,
+            html_encode(&entry.file_name)
+        ));
+        // the squircle needs an svg clip path, css has no simple superellipse
+        html.push_str(
+            r#"<svg width="0" height="0"><clipPath id="clip_squircle" clipPathUnits="objectBoundingBox">
+            <path d="M 0.5,0 C 0.1,0 0,0.1 0,0.5 S 0.1,1 0.5,1 S 1,0.9 1,0.5 S 0.9,0 0.5,0 Z"/></clipPath></svg>"#,
+        );
+        for mask in ["circle", "squircle", "rounded_square", "teardrop"].iter() {
+            html.push_str(&format!(
+                r#"<figure><img class="mask_{0}" src="{1}" width="128" height="128"/><figcaption>{0}</figcaption></figure>"#,
+                mask, url
+            ));
+        }
+        html.push_str("</div>");
+    }
+    // return
+    html
+}
+
+/// the bytes before and after the png optimization, the details per file are folded
+pub fn html_for_png_report(png_sizes: &[PngSize]) -> String {
+    let mut html = format!(
+        r#"<details><summary>PNG optimization: {}</summary><table><tr><th>file</th><th>before</th><th>after</th></tr>"#,
+        html_encode(&png_report_summary(png_sizes))
+    );
+    for png_size in png_sizes.iter() {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            html_encode(&png_size.path),
+            png_size.bytes_before,
+            png_size.bytes_after
+        ));
+    }
+    html.push_str("</table></details>");
+    // return
+    html
+}
+
+/// read input elements and save to local storage
+pub fn read_input_elements_and_save_to_local_storage() -> Result<PwaData, HelperError> {
+    let pwa_data = PwaData {
+        pwa_short_name: get_input_element_value_string_by_id("pwa_short_name")?,
+        pwa_name: get_input_element_value_string_by_id("pwa_name")?,
+        pwa_folder: get_input_element_value_string_by_id("pwa_folder")?,
+        pwa_description: get_input_element_value_string_by_id("pwa_description")?,
+        pwa_icon_set: get_textarea_element_value_string_by_id("pwa_icon_set")?,
+        pwa_resize_filter: get_select_element_value_string_by_id("pwa_resize_filter")?,
+        pwa_linear_light: get_input_element_checked_by_id("pwa_linear_light")?,
+        pwa_sharpen: get_input_element_value_string_by_id("pwa_sharpen")?,
+        pwa_png_optimization: get_input_element_checked_by_id("pwa_png_optimization")?,
+        pwa_webp: get_input_element_checked_by_id("pwa_webp")?,
+        pwa_avif: get_input_element_checked_by_id("pwa_avif")?,
+        pwa_crop_rect: get_input_element_value_string_by_id("pwa_crop_rect")?,
+        pwa_source_fix: get_select_element_value_string_by_id("pwa_source_fix")?,
+        pwa_maskable_background: get_input_element_value_string_by_id("pwa_maskable_background")?,
+        pwa_image_compression: get_select_element_value_string_by_id("pwa_image_compression")?,
+        pwa_id: get_input_element_value_string_by_id("pwa_id")?,
+        pwa_start_url: get_input_element_value_string_by_id("pwa_start_url")?,
+        pwa_scope: get_input_element_value_string_by_id("pwa_scope")?,
+        pwa_lang: get_input_element_value_string_by_id("pwa_lang")?,
+        pwa_dir: get_select_element_value_string_by_id("pwa_dir")?,
+        pwa_display: get_select_element_value_string_by_id("pwa_display")?,
+        pwa_display_override: get_input_element_value_string_by_id("pwa_display_override")?,
+        pwa_orientation: get_select_element_value_string_by_id("pwa_orientation")?,
+        pwa_background_color: get_input_element_value_string_by_id("pwa_background_color")?,
+        pwa_theme_color: get_input_element_value_string_by_id("pwa_theme_color")?,
+        pwa_categories: get_input_element_value_string_by_id("pwa_categories")?,
+        pwa_iarc_rating_id: get_input_element_value_string_by_id("pwa_iarc_rating_id")?,
+        pwa_shortcuts: get_textarea_element_value_string_by_id("pwa_shortcuts")?,
+        pwa_screenshots: get_textarea_element_value_string_by_id("pwa_screenshots")?,
+        pwa_related_applications: get_textarea_element_value_string_by_id("pwa_related_applications")?,
+        pwa_prefer_related_applications: get_input_element_checked_by_id("pwa_prefer_related_applications")?,
+        pwa_caching_rules: get_textarea_element_value_string_by_id("pwa_caching_rules")?,
+        pwa_precache_extra: get_textarea_element_value_string_by_id("pwa_precache_extra")?,
+        pwa_offline_page: get_input_element_checked_by_id("pwa_offline_page")?,
+        pwa_cache_version: get_select_element_value_string_by_id("pwa_cache_version")?,
+        pwa_apple_splash: get_input_element_checked_by_id("pwa_apple_splash")?,
+        pwa_install_button: get_input_element_checked_by_id("pwa_install_button")?,
+        pwa_update_flow: get_select_element_value_string_by_id("pwa_update_flow")?,
+        pwa_update_banner_text: get_input_element_value_string_by_id("pwa_update_banner_text")?,
+    };
+    save_to_local_storage("pwa_short_name", &pwa_data.pwa_short_name)?;
+    save_to_local_storage("pwa_name", &pwa_data.pwa_name)?;
+    save_to_local_storage("pwa_folder", &pwa_data.pwa_folder)?;
+    save_to_local_storage("pwa_description", &pwa_data.pwa_description)?;
+    save_to_local_storage("pwa_icon_set", &pwa_data.pwa_icon_set)?;
+    save_to_local_storage("pwa_resize_filter", &pwa_data.pwa_resize_filter)?;
+    save_to_local_storage("pwa_linear_light", &pwa_data.pwa_linear_light.to_string())?;
+    save_to_local_storage("pwa_sharpen", &pwa_data.pwa_sharpen)?;
+    save_to_local_storage("pwa_png_optimization", &pwa_data.pwa_png_optimization.to_string())?;
+    save_to_local_storage("pwa_webp", &pwa_data.pwa_webp.to_string())?;
+    save_to_local_storage("pwa_avif", &pwa_data.pwa_avif.to_string())?;
+    save_to_local_storage("pwa_crop_rect", &pwa_data.pwa_crop_rect)?;
+    save_to_local_storage("pwa_source_fix", &pwa_data.pwa_source_fix)?;
+    save_to_local_storage("pwa_maskable_background", &pwa_data.pwa_maskable_background)?;
+    save_to_local_storage("pwa_image_compression", &pwa_data.pwa_image_compression)?;
+    save_to_local_storage("pwa_id", &pwa_data.pwa_id)?;
+    save_to_local_storage("pwa_start_url", &pwa_data.pwa_start_url)?;
+    save_to_local_storage("pwa_scope", &pwa_data.pwa_scope)?;
+    save_to_local_storage("pwa_lang", &pwa_data.pwa_lang)?;
+    save_to_local_storage("pwa_dir", &pwa_data.pwa_dir)?;
+    save_to_local_storage("pwa_display", &pwa_data.pwa_display)?;
+    save_to_local_storage("pwa_display_override", &pwa_data.pwa_display_override)?;
+    save_to_local_storage("pwa_orientation", &pwa_data.pwa_orientation)?;
+    save_to_local_storage("pwa_background_color", &pwa_data.pwa_background_color)?;
+    save_to_local_storage("pwa_theme_color", &pwa_data.pwa_theme_color)?;
+    save_to_local_storage("pwa_categories", &pwa_data.pwa_categories)?;
+    save_to_local_storage("pwa_iarc_rating_id", &pwa_data.pwa_iarc_rating_id)?;
+    save_to_local_storage("pwa_shortcuts", &pwa_data.pwa_shortcuts)?;
+    save_to_local_storage("pwa_screenshots", &pwa_data.pwa_screenshots)?;
+    save_to_local_storage("pwa_related_applications", &pwa_data.pwa_related_applications)?;
+    save_to_local_storage(
+        "pwa_prefer_related_applications",
+        &pwa_data.pwa_prefer_related_applications.to_string(),
+    )?;
+    save_to_local_storage("pwa_caching_rules", &pwa_data.pwa_caching_rules)?;
+    save_to_local_storage("pwa_precache_extra", &pwa_data.pwa_precache_extra)?;
+    save_to_local_storage("pwa_offline_page", &pwa_data.pwa_offline_page.to_string())?;
+    save_to_local_storage("pwa_cache_version", &pwa_data.pwa_cache_version)?;
+    save_to_local_storage("pwa_apple_splash", &pwa_data.pwa_apple_splash.to_string())?;
+    save_to_local_storage("pwa_install_button", &pwa_data.pwa_install_button.to_string())?;
+    save_to_local_storage("pwa_update_flow", &pwa_data.pwa_update_flow)?;
+    save_to_local_storage("pwa_update_banner_text", &pwa_data.pwa_update_banner_text)?;
+    // return
+    Ok(pwa_data)
+}